@@ -1,8 +1,11 @@
 use bevy::{audio::*, prelude::*};
 use bevy_asset_loader::prelude::*;
+use tetris::Spin;
 
 use crate::{
-    bricks::LinesRemoved, controls::ControlEvent, shape::ShapeSpawned, GameState, GameStats,
+    bricks::LinesCleared,
+    shape::{ShapeHardDropped, ShapeLocked, ShapeMoved},
+    GameState, GameStats,
 };
 
 pub struct AudioPlugin;
@@ -62,34 +65,49 @@ fn game_over(assets: Res<SoundAssets>, audio: Res<Audio>) {
 fn sound_effects(
     assets: Res<SoundAssets>,
     audio: Res<Audio>,
-    mut controls: EventReader<ControlEvent>,
-    mut shapes: EventReader<ShapeSpawned>,
-    mut lines: EventReader<LinesRemoved>,
+    mut moves: EventReader<ShapeMoved>,
+    mut locks: EventReader<ShapeLocked>,
+    mut hard_drops: EventReader<ShapeHardDropped>,
+    mut clears: EventReader<LinesCleared>,
 ) {
-    if controls.iter().any(|c| {
-        [
-            ControlEvent::Left,
-            ControlEvent::Right,
-            ControlEvent::RotateLeft,
-            ControlEvent::RotateRight,
-        ]
-        .contains(c)
-    }) {
+    // only moves that succeed, holding a piece against a wall stays quiet
+    if moves.iter().last().is_some() {
         audio.play_with_settings(
             assets.rotate.cast_weak(),
             PlaybackSettings::ONCE.with_volume(0.2),
         );
     }
 
-    if shapes.iter().last().is_some() {
+    let locked = locks.iter().last().is_some();
+    if hard_drops.iter().last().is_some() {
+        audio.play_with_settings(
+            assets.drop.cast_weak(),
+            PlaybackSettings::ONCE.with_volume(0.8).with_speed(1.5),
+        );
+    } else if locked {
         audio.play_with_settings(
             assets.drop.cast_weak(),
             PlaybackSettings::ONCE.with_volume(0.4),
         );
     }
 
-    if lines.iter().last().is_some() {
-        audio.play(assets.lines.cast_weak());
+    if let Some(clear) = clears.iter().last() {
+        // TODO: play a dedicated perfect clear sample, this is a deeper and
+        // louder line clear in the meantime
+        if clear.perfect_clear {
+            audio.play_with_settings(
+                assets.lines.cast_weak(),
+                PlaybackSettings::ONCE.with_volume(1.5).with_speed(0.75),
+            );
+            return;
+        }
+
+        // spins get a brighter variant of the line clear sound
+        let speed = if clear.spin == Spin::None { 1. } else { 1.25 };
+        audio.play_with_settings(
+            assets.lines.cast_weak(),
+            PlaybackSettings::ONCE.with_speed(speed),
+        );
     }
 }
 
@@ -0,0 +1,113 @@
+use std::{collections::HashMap, fs, time::Duration};
+
+use bevy::prelude::*;
+use tetris::Record;
+
+/// Where personal bests are kept between sessions, relative to the working
+/// directory.
+pub const BESTS_PATH: &str = "personal_bests.txt";
+
+/// Best results by mode key, see `GameMode::key`. Stored as one
+/// `<key> time <nanoseconds>` or `<key> score <points>` line per mode.
+#[derive(Resource, Debug, Default)]
+pub struct PersonalBests {
+    path: Option<String>,
+    records: HashMap<String, Record>,
+}
+
+impl PersonalBests {
+    /// Reads the bests saved at `path`, a missing file starts out empty.
+    pub fn load(path: &str) -> Self {
+        let records = fs::read_to_string(path)
+            .map(|contents| parse(&contents))
+            .unwrap_or_default();
+
+        Self {
+            path: Some(path.into()),
+            records,
+        }
+    }
+
+    /// Keeps the record if it beats the best one and returns the previous best.
+    pub fn submit(&mut self, key: String, record: Record) -> Option<Record> {
+        let best = self.records.get(&key).copied();
+        if best.is_none_or(|best| record.beats(&best)) {
+            self.records.insert(key, record);
+            self.save();
+        }
+        best
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        if let Err(error) = fs::write(path, format(&self.records)) {
+            warn!("could not save personal bests to {path}: {error}");
+        }
+    }
+}
+
+fn parse(contents: &str) -> HashMap<String, Record> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.rsplitn(3, ' ');
+            let value = parts.next()?;
+            let record = match parts.next()? {
+                "time" => Record::Time(Duration::from_nanos(value.parse().ok()?)),
+                "score" => Record::Score(value.parse().ok()?),
+                _ => return None,
+            };
+            Some((parts.next()?.to_string(), record))
+        })
+        .collect()
+}
+
+fn format(records: &HashMap<String, Record>) -> String {
+    let mut lines: Vec<_> = records
+        .iter()
+        .map(|(key, record)| match record {
+            Record::Time(time) => format!("{key} time {}\n", time.as_nanos()),
+            Record::Score(score) => format!("{key} score {score}\n"),
+        })
+        .collect();
+    lines.sort();
+    lines.concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use tetris::Record;
+
+    use super::{format, parse, PersonalBests};
+
+    #[test]
+    fn records_round_trip() {
+        let records = HashMap::from([
+            (
+                "sprint-40".to_string(),
+                Record::Time(Duration::from_millis(61_500)),
+            ),
+            ("ultra-120000".to_string(), Record::Score(25_000)),
+        ]);
+
+        assert_eq!(parse(&format(&records)), records);
+        assert!(parse("garbage line\nsprint-40 time soon").is_empty());
+    }
+
+    #[test]
+    fn only_better_records_are_kept() {
+        let mut bests = PersonalBests::default();
+        let slow = Record::Time(Duration::from_secs(60));
+        let fast = Record::Time(Duration::from_secs(50));
+
+        assert_eq!(bests.submit("sprint-40".into(), slow), None);
+        assert_eq!(bests.submit("sprint-40".into(), fast), Some(slow));
+        assert_eq!(bests.submit("sprint-40".into(), slow), Some(fast));
+        assert_eq!(bests.submit("sprint-20".into(), slow), None);
+    }
+}
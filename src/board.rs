@@ -0,0 +1,205 @@
+use std::ops::Range;
+
+use crate::piece::{Piece, Tetromino};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    Piece(Tetromino),
+    Garbage,
+}
+
+#[derive(Debug, Clone)]
+pub struct Board {
+    width: i8,
+    height: i8,
+    cells: Vec<Option<Cell>>,
+}
+
+impl Board {
+    pub fn new(width: i8, height: i8) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![None; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> i8 {
+        self.width
+    }
+
+    pub fn height(&self) -> i8 {
+        self.height
+    }
+
+    pub fn columns(&self) -> Range<i8> {
+        0..self.width
+    }
+
+    pub fn rows(&self) -> Range<i8> {
+        0..self.height
+    }
+
+    pub fn get(&self, x: i8, y: i8) -> Option<Cell> {
+        self.index(x, y).and_then(|index| self.cells[index])
+    }
+
+    pub fn set(&mut self, x: i8, y: i8, cell: Option<Cell>) {
+        if let Some(index) = self.index(x, y) {
+            self.cells[index] = cell;
+        }
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = ((i8, i8), Cell)> + '_ {
+        self.rows()
+            .flat_map(move |y| self.columns().map(move |x| (x, y)))
+            .filter_map(|(x, y)| self.get(x, y).map(|cell| ((x, y), cell)))
+    }
+
+    /// Walls and floor count as occupied, cells above the top row are free so
+    /// pieces can spawn and rotate there.
+    pub fn is_occupied(&self, x: i8, y: i8) -> bool {
+        !self.columns().contains(&x) || y < 0 || self.get(x, y).is_some()
+    }
+
+    pub fn collides(&self, piece: &Piece) -> bool {
+        piece.cells().any(|(x, y)| self.is_occupied(x, y))
+    }
+
+    pub fn place(&mut self, piece: &Piece) {
+        let cell = Cell::Piece(piece.tetromino);
+        for (x, y) in piece.cells() {
+            self.set(x, y, Some(cell));
+        }
+    }
+
+    pub fn full_rows(&self) -> Vec<i8> {
+        self.rows()
+            .filter(|&y| self.columns().all(|x| self.get(x, y).is_some()))
+            .collect()
+    }
+
+    pub fn has_garbage(&self, y: i8) -> bool {
+        self.columns()
+            .any(|x| self.get(x, y) == Some(Cell::Garbage))
+    }
+
+    /// Pushes the stack up by one row and fills the bottom row with garbage,
+    /// except for the holes. Returns false if blocks were pushed off the top.
+    pub fn push_garbage(&mut self, holes: &[i8]) -> bool {
+        let top = self.cells.len() - self.width as usize;
+        let overflow = self.cells[top..].iter().any(Option::is_some);
+        self.cells.truncate(top);
+
+        let row: Vec<_> = self
+            .columns()
+            .map(|x| (!holes.contains(&x)).then_some(Cell::Garbage))
+            .collect();
+        self.cells.splice(0..0, row);
+
+        !overflow
+    }
+
+    /// Removes all full rows, moves the rows above them down and returns the
+    /// number of rows removed.
+    pub fn clear_lines(&mut self) -> u8 {
+        let width = self.width as usize;
+        let mut cells: Vec<_> = self
+            .cells
+            .chunks(width)
+            .filter(|row| !row.iter().all(Option::is_some))
+            .flatten()
+            .copied()
+            .collect();
+
+        let cleared = (self.cells.len() - cells.len()) / width;
+        cells.resize(self.cells.len(), None);
+        self.cells = cells;
+
+        cleared as u8
+    }
+
+    fn index(&self, x: i8, y: i8) -> Option<usize> {
+        if !self.columns().contains(&x) || !self.rows().contains(&y) {
+            return None;
+        }
+
+        Some(y as usize * self.width as usize + x as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Board, Cell};
+    use crate::Tetromino;
+
+    const CELL: Cell = Cell::Piece(Tetromino::T);
+
+    fn fill_row(board: &mut Board, y: i8) {
+        for x in board.columns() {
+            board.set(x, y, Some(CELL));
+        }
+    }
+
+    #[test]
+    fn even_width() {
+        let mut board = Board::new(10, 20);
+        board.set(0, 0, Some(CELL));
+        board.set(9, 0, Some(CELL));
+
+        assert_eq!(board.columns(), 0..10);
+        assert_eq!(board.cells().count(), 2);
+    }
+
+    #[test]
+    fn out_of_range_is_empty() {
+        let mut board = Board::new(10, 20);
+        board.set(-1, 0, Some(CELL));
+        board.set(10, 0, Some(CELL));
+        board.set(0, 20, Some(CELL));
+
+        assert_eq!(board.cells().count(), 0);
+    }
+
+    #[test]
+    fn clear_lines_moves_rows_down() {
+        let mut board = Board::new(10, 20);
+        fill_row(&mut board, 0);
+        board.set(2, 1, Some(CELL));
+        fill_row(&mut board, 2);
+        board.set(3, 3, Some(CELL));
+
+        assert_eq!(board.full_rows(), vec![0, 2]);
+        assert_eq!(board.clear_lines(), 2);
+        assert_eq!(
+            board
+                .cells()
+                .map(|(position, _)| position)
+                .collect::<Vec<_>>(),
+            vec![(2, 0), (3, 1)]
+        );
+    }
+
+    #[test]
+    fn garbage_pushes_stack_up() {
+        let mut board = Board::new(10, 4);
+        board.set(5, 0, Some(CELL));
+
+        assert!(board.push_garbage(&[2, 7]));
+        assert_eq!(board.get(5, 1), Some(CELL));
+        assert!(board.has_garbage(0));
+        assert_eq!(board.get(2, 0), None);
+        assert_eq!(board.get(7, 0), None);
+        assert_eq!(
+            board
+                .cells()
+                .filter(|&(_, cell)| cell == Cell::Garbage)
+                .count(),
+            8
+        );
+
+        board.set(0, 3, Some(CELL));
+        assert!(!board.push_garbage(&[0]));
+        assert_eq!(board.height(), 4);
+    }
+}
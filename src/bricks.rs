@@ -1,8 +1,7 @@
-use bevy::{prelude::*, utils::hashbrown::HashMap};
+use bevy::prelude::*;
+use tetris::{Cell, Config, LineClear, Tetromino};
 
-use crate::{
-    GameState, BRICK_COLS_RANGE, BRICK_ROWS, BRICK_ROWS_RANGE, BRICK_SIZE, OFFSET_X, OFFSET_Y,
-};
+use crate::{Engine, GameState, Settings, BRICK_SIZE, OFFSET_X, OFFSET_Y};
 
 #[derive(Component, Default, Clone, Debug, Reflect)]
 #[reflect(Component)]
@@ -11,40 +10,45 @@ pub struct Brick {
     pub y: i8,
 }
 
-#[derive(Resource, Default, Debug, Deref, DerefMut)]
-pub struct Bricks(pub HashMap<(i8, i8), Entity>);
+const GARBAGE_COLOR: Color = Color::GRAY;
 
+#[derive(Resource, Debug, Clone)]
+pub struct TetrominoColors(pub [Color; 7]);
+
+impl Default for TetrominoColors {
+    fn default() -> Self {
+        // guideline colors, in the order of `Tetromino::ALL`
+        Self([180., 60., 285., 120., 0., 230., 30.].map(|hue| Color::hsl(hue, 1.0, 0.6)))
+    }
+}
+
+impl TetrominoColors {
+    pub fn get(&self, tetromino: Tetromino) -> Color {
+        self.0[tetromino as usize]
+    }
+}
+
+/// A piece locked and cleared lines or spun into place.
 #[derive(Debug, Clone, Deref)]
-pub struct LinesRemoved(u8);
+pub struct LinesCleared(pub LineClear);
 
 pub struct BrickPlugin;
 
 impl Plugin for BrickPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<LinesRemoved>()
-            .init_resource::<Bricks>()
+        app.add_event::<LinesCleared>()
+            .init_resource::<TetrominoColors>()
             .register_type::<Brick>()
-            .add_systems(
-                (remove_lines, move_lines_down)
-                    .chain()
-                    .in_set(OnUpdate(GameState::InGame))
-                    .in_schedule(CoreSchedule::FixedUpdate),
-            )
-            .add_system(reset.in_schedule(OnEnter(GameState::InGame)));
+            .add_system(reset.in_schedule(OnEnter(GameState::Starting)))
+            .add_system(update_bricks.run_if(resource_exists::<Engine>()));
     }
 }
 
-pub fn to_brick_coordinates(translation: Vec3) -> (i8, i8) {
-    let x = ((translation.x - OFFSET_X) / BRICK_SIZE).round() as i8;
-    let y = (((translation.y - OFFSET_Y) + BRICK_ROWS as f32 / 2. * BRICK_SIZE) / BRICK_SIZE)
-        .round() as i8;
-    (x, y)
-}
-
-pub fn to_brick_translation(x: i8, y: i8) -> Vec3 {
+/// The visible rows of the board are centered on the screen.
+pub fn to_brick_translation(config: &Config, x: i8, y: i8) -> Vec3 {
     Vec3 {
-        x: x as f32 * BRICK_SIZE + OFFSET_X,
-        y: (y as f32 - (BRICK_ROWS as f32 / 2.)) * BRICK_SIZE + OFFSET_Y,
+        x: (x as f32 - (config.width - 1) as f32 / 2.) * BRICK_SIZE + OFFSET_X,
+        y: (y as f32 - (config.height as f32 / 2.)) * BRICK_SIZE + OFFSET_Y,
         z: 1.,
     }
 }
@@ -65,119 +69,72 @@ pub fn brick_bundle(translation: Vec3, color: Color) -> SpriteBundle {
     }
 }
 
-pub fn spawn_brick(commands: &mut Commands, bricks: &mut Bricks, brick: Brick, color: Color) {
-    let entity = commands
-        .spawn(brick_bundle(to_brick_translation(brick.x, brick.y), color))
-        .insert(brick.clone())
-        .id();
-
-    bricks.0.insert((brick.x, brick.y), entity);
-}
-
-pub fn remove_lines(
-    mut commands: Commands,
-    mut bricks: ResMut<Bricks>,
-    mut events: EventWriter<LinesRemoved>,
+fn update_bricks(
+    engine: Res<Engine>,
+    colors: Res<TetrominoColors>,
+    mut query: Query<(&Brick, &mut Sprite, &mut Visibility)>,
 ) {
-    if !bricks.is_changed() {
+    if !engine.is_changed() && !colors.is_changed() {
         return;
     }
 
-    let mut removed_lines = 0;
-
-    for y in BRICK_ROWS_RANGE {
-        if BRICK_COLS_RANGE
-            .clone()
-            .all(|x| bricks.contains_key(&(x, y)))
-        {
-            remove_line(&mut commands, &mut bricks, y);
-            removed_lines += 1;
-        }
-    }
-
-    if removed_lines > 0 {
-        events.send(LinesRemoved(removed_lines))
-    }
-}
-
-pub fn remove_line(commands: &mut Commands, bricks: &mut Bricks, y: i8) {
-    for x in BRICK_COLS_RANGE {
-        let coords = (x, y);
-        let entity = bricks.remove(&coords);
-
-        if let Some(entity) = entity {
-            commands.entity(entity).despawn();
+    let clearing = engine.clearing_rows();
+    for (brick, mut sprite, mut visibility) in &mut query {
+        match engine.board().get(brick.x, brick.y) {
+            Some(_) if clearing.contains(&brick.y) => {
+                sprite.color = Color::WHITE;
+                *visibility = Visibility::Visible;
+            }
+            Some(Cell::Piece(tetromino)) => {
+                sprite.color = colors.get(tetromino);
+                *visibility = Visibility::Visible;
+            }
+            Some(Cell::Garbage) => {
+                sprite.color = GARBAGE_COLOR;
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
         }
     }
 }
 
-pub fn move_lines_down(
-    mut bricks: ResMut<Bricks>,
-    mut done: Local<bool>,
-    mut query: Query<(&mut Brick, &mut Transform)>,
-) {
-    if *done && !bricks.is_changed() {
-        return;
-    } else {
-        // do not run again unless there's a change
-        *done = true;
+fn reset(mut commands: Commands, settings: Res<Settings>, query: Query<Entity, With<Brick>>) {
+    for entity in &query {
+        commands.entity(entity).despawn();
     }
 
-    let mut from_ys = BRICK_ROWS_RANGE.into_iter();
-    for to_y in BRICK_ROWS_RANGE {
-        if BRICK_COLS_RANGE
-            .clone()
-            .all(|x| !bricks.contains_key(&(x, to_y)))
-        {
-            while let Some(from_y) = from_ys.next() {
-                if from_y > to_y
-                    && BRICK_COLS_RANGE
-                        .clone()
-                        .any(|x| bricks.contains_key(&(x, from_y)))
-                {
-                    for x in BRICK_COLS_RANGE {
-                        let coords = (x, from_y);
-                        let entity = bricks.remove(&coords);
-
-                        if let Some(entity) = entity {
-                            let (mut brick, mut transform) =
-                                query.get_mut(entity).expect("no such entity");
-                            let new_coords = (x, to_y);
-
-                            // run again
-                            *done = false;
-
-                            bricks.insert(new_coords, entity);
-                            brick.y = to_y;
-                            transform.translation = to_brick_translation(brick.x, brick.y);
-                        }
-                    }
-                    break; // proceed to next empty line
-                }
-            }
-            return; // nothing left to move
+    let config = settings.config();
+    for y in 0..config.height {
+        for x in 0..config.width {
+            commands
+                .spawn(brick_bundle(
+                    to_brick_translation(&config, x, y),
+                    Color::NONE,
+                ))
+                .insert(Visibility::Hidden)
+                .insert(Brick { x, y });
         }
     }
 }
 
-pub fn reset(mut commands: Commands, mut query: Query<Entity, With<Brick>>) {
-    commands.insert_resource(Bricks::default());
-
-    for entity in &mut query {
-        commands.entity(entity).despawn();
-    }
-}
-
 #[cfg(test)]
 mod tests {
-    use crate::BRICK_ROWS;
     use bevy::prelude::*;
+    use tetris::Config;
+
+    use super::to_brick_translation;
 
-    use super::{to_brick_coordinates, to_brick_translation};
+    fn config(width: i8, height: i8) -> Config {
+        Config {
+            width,
+            height,
+            ..Config::default()
+        }
+    }
 
     #[test]
     fn screen_center() {
-        let translation = to_brick_translation(0, BRICK_ROWS / 2);
+        let translation = to_brick_translation(&config(11, 20), 5, 10);
 
         assert_eq!(
             translation,
@@ -189,25 +146,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn even_width() {
+        let translation = to_brick_translation(&config(10, 20), 5, 10);
+
+        assert_eq!(translation.x, 15.);
+    }
+
     #[test]
     fn top() {
-        let translation = to_brick_translation(0, BRICK_ROWS);
+        let translation = to_brick_translation(&config(10, 20), 0, 20);
 
         assert_eq!(
             translation,
             Vec3 {
-                x: 0.,
+                x: -135.,
                 y: 300.,
                 z: 1.
             }
         );
     }
-
-    #[test]
-    fn convert_and_back() {
-        let translation = to_brick_translation(-3, 7);
-        let coords = to_brick_coordinates(translation);
-
-        assert_eq!(coords, (-3, 7));
-    }
 }
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use crate::{gravity::Gravity, randomizer::RandomizerKind};
+
+pub const MAX_PREVIEW: usize = 6;
+
+/// When the game clock starts running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Start {
+    #[default]
+    Immediately,
+    /// Nothing moves until the first input.
+    FirstInput,
+    /// Inputs are buffered until the countdown ends.
+    Countdown(Duration),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub width: i8,
+    /// Number of visible rows.
+    pub height: i8,
+    /// Hidden rows above the visible ones, for stacks rising above the top.
+    pub buffer: i8,
+    pub randomizer: RandomizerKind,
+    pub start: Start,
+    /// Number of upcoming pieces to reveal, between 1 and `MAX_PREVIEW`.
+    pub preview: usize,
+    /// Time a piece may rest on the stack before it locks.
+    pub lock_delay: Duration,
+    /// Number of moves and rotations that restart the lock delay, per row.
+    pub lock_resets: u8,
+    /// Falling speed for each level.
+    pub gravity: Gravity,
+    /// Time between a piece locking and the next one appearing (ARE).
+    pub entry_delay: Duration,
+    /// Time full rows stay on the board before they are removed.
+    pub line_clear_delay: Duration,
+    /// Soft drop speed as a multiple of the regular gravity.
+    pub soft_drop_factor: f32,
+    pub start_level: u32,
+    /// End the game when a piece locks with any of its cells above the visible
+    /// rows, instead of only when all of them are.
+    pub partial_lock_out: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            width: 10,
+            height: 20,
+            buffer: 20,
+            randomizer: RandomizerKind::default(),
+            start: Start::default(),
+            preview: 5,
+            lock_delay: Duration::from_millis(500),
+            lock_resets: 15,
+            gravity: Gravity::default(),
+            entry_delay: Duration::ZERO,
+            line_clear_delay: Duration::ZERO,
+            soft_drop_factor: 20.,
+            start_level: 1,
+            partial_lock_out: false,
+        }
+    }
+}
@@ -1,5 +1,9 @@
+use std::time::Duration;
+
 use bevy::prelude::*;
 
+use crate::{shape::ShapeSpawned, Settings};
+
 pub struct ControlsPlugin;
 
 impl Plugin for ControlsPlugin {
@@ -13,22 +17,125 @@ pub enum ControlEvent {
     SpeedupStart,
     SpeedupEnd,
     Pause,
+    ToggleGhost,
     Left,
     Right,
+    LeftToWall,
+    RightToWall,
     RotateRight,
     RotateLeft,
+    HardDrop,
+    Hold,
+}
+
+/// How simultaneous left and right inputs are resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Socd {
+    /// The most recently pressed direction takes over.
+    #[default]
+    LastWins,
+    /// The direction held first keeps going until it is released.
+    FirstWins,
+    /// Holding both directions stops the piece.
+    Neutral,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Left,
+    Right,
+}
+
+impl Socd {
+    fn resolve(self, left: bool, right: bool, last: Direction) -> Option<Direction> {
+        match (left, right) {
+            (false, false) => None,
+            (true, false) => Some(Direction::Left),
+            (false, true) => Some(Direction::Right),
+            (true, true) => match (self, last) {
+                (Socd::LastWins, direction) => Some(direction),
+                (Socd::FirstWins, Direction::Left) => Some(Direction::Right),
+                (Socd::FirstWins, Direction::Right) => Some(Direction::Left),
+                (Socd::Neutral, _) => None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Handling {
+    /// Delayed auto shift: how long a direction is held before it repeats.
+    pub das: Duration,
+    /// Auto repeat rate: time between repeated moves, zero moves to the wall.
+    pub arr: Duration,
+    /// DAS cut delay: pause of the auto repeat after a rotation or new piece.
+    pub dcd: Duration,
+    pub socd: Socd,
+}
+
+impl Default for Handling {
+    fn default() -> Self {
+        Self {
+            das: Duration::from_millis(300),
+            arr: Duration::from_millis(100),
+            dcd: Duration::ZERO,
+            socd: Socd::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct AutoShift {
+    held: Duration,
+    repeat: Duration,
+    charged: bool,
+}
+
+#[derive(Debug)]
+struct AutoRepeat {
+    left: AutoShift,
+    right: AutoShift,
+    last: Direction,
+    active: Option<Direction>,
+    cut: Duration,
+}
+
+impl Default for AutoRepeat {
+    fn default() -> Self {
+        Self {
+            left: AutoShift::default(),
+            right: AutoShift::default(),
+            last: Direction::Right,
+            active: None,
+            cut: Duration::ZERO,
+        }
+    }
 }
 
 fn controls(
     keys: Res<Input<KeyCode>>,
     mut events: EventWriter<ControlEvent>,
     time: Res<Time>,
-    mut repeat_timer: Local<Timer>,
+    settings: Res<Settings>,
+    mut spawned: EventReader<ShapeSpawned>,
+    mut repeat: Local<AutoRepeat>,
 ) {
+    let handling = &settings.handling;
+    let repeat = &mut *repeat;
+
+    repeat.cut = repeat.cut.saturating_sub(time.delta());
+    if spawned.iter().last().is_some() {
+        repeat.cut = handling.dcd;
+    }
+
     if keys.just_pressed(KeyCode::Space) {
         events.send(ControlEvent::Pause);
     }
 
+    if keys.just_pressed(KeyCode::G) {
+        events.send(ControlEvent::ToggleGhost);
+    }
+
     if keys.just_pressed(KeyCode::Down) {
         events.send(ControlEvent::SpeedupStart);
     }
@@ -37,26 +144,112 @@ fn controls(
         events.send(ControlEvent::SpeedupEnd);
     }
 
-    let shift = keys.any_pressed([KeyCode::LShift, KeyCode::RShift]);
-    let mut handle_repeating_key = |key_code: KeyCode, control_event: ControlEvent, delay: f32| {
+    if keys.just_pressed(KeyCode::Return) {
+        events.send(ControlEvent::HardDrop);
+    }
+
+    if keys.just_pressed(KeyCode::C) {
+        events.send(ControlEvent::Hold);
+        repeat.cut = handling.dcd;
+    }
+
+    if keys.just_pressed(KeyCode::Up) {
+        if keys.any_pressed([KeyCode::LShift, KeyCode::RShift]) {
+            events.send(ControlEvent::RotateLeft);
+        } else {
+            events.send(ControlEvent::RotateRight);
+        }
+        repeat.cut = handling.dcd;
+    }
+
+    // both directions keep charging while held, so DAS carries over when the
+    // other direction is released
+    for (key_code, direction, state) in [
+        (KeyCode::Left, Direction::Left, &mut repeat.left),
+        (KeyCode::Right, Direction::Right, &mut repeat.right),
+    ] {
         if keys.just_pressed(key_code) {
-            events.send(control_event);
-            *repeat_timer = Timer::from_seconds(delay, TimerMode::Once);
-            true
+            *state = AutoShift::default();
+            repeat.last = direction;
         } else if keys.pressed(key_code) {
-            repeat_timer.tick(time.delta());
-            if repeat_timer.just_finished() {
-                events.send(control_event);
-                *repeat_timer = Timer::from_seconds(0.1, TimerMode::Once);
-            }
-            true
-        } else {
-            false
+            state.held += time.delta();
         }
+    }
+
+    let active = handling.socd.resolve(
+        keys.pressed(KeyCode::Left),
+        keys.pressed(KeyCode::Right),
+        repeat.last,
+    );
+    let changed = active != repeat.active;
+    repeat.active = active;
+
+    let (key_code, state, control_event, to_wall_event) = match active {
+        None => return,
+        Some(Direction::Left) => (
+            KeyCode::Left,
+            &mut repeat.left,
+            ControlEvent::Left,
+            ControlEvent::LeftToWall,
+        ),
+        Some(Direction::Right) => (
+            KeyCode::Right,
+            &mut repeat.right,
+            ControlEvent::Right,
+            ControlEvent::RightToWall,
+        ),
     };
 
-    let _ = handle_repeating_key(KeyCode::Right, ControlEvent::Right, 0.3)
-        || handle_repeating_key(KeyCode::Left, ControlEvent::Left, 0.3)
-        || handle_repeating_key(KeyCode::Up, ControlEvent::RotateRight, 0.6)
-        || (shift && handle_repeating_key(KeyCode::Up, ControlEvent::RotateLeft, 0.6));
+    if keys.just_pressed(key_code) {
+        events.send(control_event);
+        return;
+    }
+
+    if changed {
+        state.repeat = Duration::ZERO;
+        state.charged = false;
+    }
+
+    if state.held < handling.das || !repeat.cut.is_zero() {
+        return;
+    }
+
+    if handling.arr.is_zero() {
+        events.send(to_wall_event);
+    } else if !state.charged {
+        events.send(control_event);
+        state.charged = true;
+    } else {
+        state.repeat += time.delta();
+        while state.repeat >= handling.arr {
+            events.send(control_event);
+            state.repeat -= handling.arr;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Direction, Socd};
+
+    #[test]
+    fn socd_resolution() {
+        for socd in [Socd::LastWins, Socd::FirstWins, Socd::Neutral] {
+            assert_eq!(socd.resolve(false, false, Direction::Left), None);
+            assert_eq!(
+                socd.resolve(true, false, Direction::Right),
+                Some(Direction::Left)
+            );
+        }
+
+        assert_eq!(
+            Socd::LastWins.resolve(true, true, Direction::Left),
+            Some(Direction::Left)
+        );
+        assert_eq!(
+            Socd::FirstWins.resolve(true, true, Direction::Left),
+            Some(Direction::Right)
+        );
+        assert_eq!(Socd::Neutral.resolve(true, true, Direction::Left), None);
+    }
 }
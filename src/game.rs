@@ -0,0 +1,1208 @@
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+use crate::{
+    board::Board,
+    config::{Config, Start, MAX_PREVIEW},
+    garbage::Garbage,
+    mode::{Endless, GameMode, Goal, Outcome, Record, Summary},
+    piece::{Piece, Rotation, Tetromino, Turn},
+    randomizer::Randomizer,
+    scoring::{LineClear, Scoring, Spin},
+};
+
+/// Game logic advances in fixed steps of this rate, independent of the frame
+/// rate, so the same seed and inputs always lead to the same game.
+pub const TICKS_PER_SECOND: u32 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Left,
+    Right,
+    /// Moves the active piece as far left as it goes.
+    LeftToWall,
+    /// Moves the active piece as far right as it goes.
+    RightToWall,
+    RotateRight,
+    RotateLeft,
+    SoftDropStart,
+    SoftDropEnd,
+    HardDrop,
+    Hold,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    /// The active piece moved sideways or rotated.
+    Moved,
+    /// The active piece became part of the stack.
+    Locked,
+    /// The next piece entered the board after a lock.
+    Spawned,
+    /// The active piece was swapped with the held one.
+    Held,
+    /// The active piece was dropped by the given number of rows and locked.
+    HardDropped(u8),
+    /// A piece locked and cleared lines, or spun into place without clearing.
+    Cleared(LineClear),
+    GameOver,
+}
+
+/// What the game is waiting for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Phase {
+    /// A piece is falling and responds to input.
+    #[default]
+    Falling,
+    /// Full rows are shown before they are removed.
+    LineClear,
+    /// The next piece is about to appear (ARE).
+    Entry,
+    /// The first piece waits for the first input before it falls.
+    Ready,
+    /// The first piece waits for the countdown to end.
+    Countdown,
+}
+
+/// Guideline reasons for a game to end by topping out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TopOut {
+    /// A new piece overlaps the stack where it spawns.
+    BlockOut,
+    /// A piece locked entirely above the visible rows.
+    LockOut,
+    /// A piece locked partly above the visible rows.
+    PartialLockOut,
+    /// Garbage pushed the stack above the top of the board.
+    GarbageOut,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct LockDelay {
+    ticks: u32,
+    resets: u8,
+    lowest_y: i8,
+    landed: bool,
+}
+
+impl LockDelay {
+    fn new(y: i8) -> Self {
+        Self {
+            ticks: 0,
+            resets: 0,
+            lowest_y: y,
+            landed: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Game {
+    config: Config,
+    mode: Arc<dyn GameMode>,
+    board: Board,
+    piece: Piece,
+    phase: Phase,
+    /// Ticks left in the current delay phase.
+    delay: u32,
+    /// Actions received during a delay, applied once the next piece spawns.
+    buffered: Vec<Action>,
+    held: Option<Tetromino>,
+    hold_used: bool,
+    queue: VecDeque<Tetromino>,
+    randomizer: Box<dyn Randomizer>,
+    garbage: Garbage,
+    /// Rows holding garbage that were cleared so far.
+    garbage_cleared: u32,
+    seed: u64,
+    ticks: u64,
+    pieces: usize,
+    gravity: f32,
+    soft_drop: bool,
+    lock_delay: LockDelay,
+    /// The wall kick used, if the last successful move was a rotation.
+    last_kick: Option<usize>,
+    scoring: Scoring,
+    events: Vec<GameEvent>,
+    outcome: Option<Outcome>,
+}
+
+impl Game {
+    /// Every random choice in the game is derived from `seed`.
+    pub fn new(config: Config, seed: u64) -> Self {
+        Self::with_mode(config, seed, Arc::new(Endless))
+    }
+
+    /// The mode adjusts the config before the game starts.
+    pub fn with_mode(mut config: Config, seed: u64, mode: Arc<dyn GameMode>) -> Self {
+        mode.configure(&mut config);
+
+        let mut randomizer = config.randomizer.build(seed);
+        let first = randomizer.next();
+        let piece = Piece::new(first, spawn_column(config.width), config.height);
+        let queue = (0..config.preview.clamp(1, MAX_PREVIEW))
+            .map(|_| randomizer.next())
+            .collect();
+
+        let mut game = Self {
+            board: Board::new(config.width, config.height + config.buffer),
+            lock_delay: LockDelay::new(piece.y),
+            last_kick: None,
+            scoring: Scoring::new(config.start_level),
+            config,
+            mode,
+            piece,
+            phase: Phase::Falling,
+            delay: 0,
+            buffered: Vec::new(),
+            held: None,
+            hold_used: false,
+            queue,
+            randomizer,
+            garbage: Garbage::new(seed),
+            garbage_cleared: 0,
+            seed,
+            ticks: 0,
+            pieces: 0,
+            gravity: 0.,
+            soft_drop: false,
+            events: Vec::new(),
+            outcome: None,
+        };
+        game.spawn(first);
+        let mode = game.mode.clone();
+        mode.setup(&mut game);
+
+        match game.config.start {
+            Start::Immediately => (),
+            Start::FirstInput => game.phase = Phase::Ready,
+            Start::Countdown(duration) => {
+                game.delay = to_ticks(duration);
+                if game.delay > 0 {
+                    game.phase = Phase::Countdown;
+                }
+            }
+        }
+        game
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Number of fixed steps the game has advanced.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// Number of pieces locked so far.
+    pub fn pieces(&self) -> usize {
+        self.pieces
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn garbage_cleared(&self) -> u32 {
+        self.garbage_cleared
+    }
+
+    /// Pushes the stack up by `rows` garbage rows with one hole each, see
+    /// `Garbage::next_hole` for the messiness.
+    pub fn add_garbage(&mut self, rows: u32, messiness: f32) {
+        for _ in 0..rows {
+            let hole = self.garbage.next_hole(self.config.width, messiness);
+            self.add_garbage_row(&[hole]);
+        }
+    }
+
+    /// Pushes the stack up by a garbage row with holes in the given columns.
+    /// The active piece moves up with the stack if it would overlap it.
+    pub fn add_garbage_row(&mut self, holes: &[i8]) {
+        if self.is_over() {
+            return;
+        }
+        if !self.board.push_garbage(holes) {
+            self.end(Outcome::ToppedOut(TopOut::GarbageOut));
+            return;
+        }
+
+        while self.board.collides(&self.piece) {
+            self.piece.y += 1;
+        }
+    }
+
+    /// The falling piece, or the one that locked last during delays.
+    pub fn piece(&self) -> &Piece {
+        &self.piece
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Full rows waiting to be removed during the line clear delay.
+    pub fn clearing_rows(&self) -> Vec<i8> {
+        match self.phase {
+            Phase::LineClear => self.board.full_rows(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The upcoming pieces, in the order they will be dealt.
+    pub fn next_pieces(&self) -> impl Iterator<Item = Tetromino> + '_ {
+        self.queue.iter().copied()
+    }
+
+    pub fn held(&self) -> Option<Tetromino> {
+        self.held
+    }
+
+    /// Hold can be used once per piece, until it locks.
+    pub fn can_hold(&self) -> bool {
+        !self.hold_used
+    }
+
+    /// The active piece moved down as far as it can go.
+    pub fn ghost(&self) -> Piece {
+        let mut ghost = self.piece;
+        while !self.board.collides(&ghost.moved(0, -1)) {
+            ghost = ghost.moved(0, -1);
+        }
+        ghost
+    }
+
+    pub fn scoring(&self) -> &Scoring {
+        &self.scoring
+    }
+
+    pub fn mode(&self) -> &dyn GameMode {
+        &*self.mode
+    }
+
+    /// Time left before the game starts.
+    pub fn countdown(&self) -> Option<Duration> {
+        (self.phase == Phase::Countdown)
+            .then(|| Duration::from_secs_f64(self.delay as f64 / TICKS_PER_SECOND as f64))
+    }
+
+    /// Time played, counted in fixed steps once the game has started.
+    pub fn time(&self) -> Duration {
+        Duration::from_secs_f64(self.ticks as f64 / TICKS_PER_SECOND as f64)
+    }
+
+    /// What is left of the time limit, in modes that have one.
+    pub fn time_left(&self) -> Option<Duration> {
+        match self.mode.goal()? {
+            Goal::Time(limit) => Some(limit.saturating_sub(self.time())),
+            _ => None,
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    pub fn top_out(&self) -> Option<TopOut> {
+        match self.outcome {
+            Some(Outcome::ToppedOut(reason)) => Some(reason),
+            _ => None,
+        }
+    }
+
+    /// The result to compare against earlier games of the same mode.
+    pub fn record(&self) -> Option<Record> {
+        self.mode.record(self)
+    }
+
+    /// The results as presented by the mode, once the game is over.
+    pub fn summary(&self) -> Option<Summary> {
+        Some(self.mode.summary(self, self.outcome?))
+    }
+
+    /// Returns whether the action could be applied to the active piece.
+    /// During delays, actions are buffered and applied to the next piece.
+    pub fn apply(&mut self, action: Action) -> bool {
+        if self.is_over() {
+            return false;
+        }
+
+        if self.phase == Phase::Ready {
+            self.phase = Phase::Falling;
+        }
+
+        if self.phase != Phase::Falling {
+            match action {
+                Action::SoftDropStart | Action::SoftDropEnd => {
+                    self.soft_drop = action == Action::SoftDropStart;
+                }
+                _ => self.buffered.push(action),
+            }
+            return true;
+        }
+
+        let moved = match action {
+            Action::Left => self.try_move(self.piece.moved(-1, 0)),
+            Action::Right => self.try_move(self.piece.moved(1, 0)),
+            Action::LeftToWall => self.move_to_wall(-1),
+            Action::RightToWall => self.move_to_wall(1),
+            Action::RotateRight => self.try_rotate(Turn::Right),
+            Action::RotateLeft => self.try_rotate(Turn::Left),
+            Action::SoftDropStart | Action::SoftDropEnd => {
+                self.soft_drop = action == Action::SoftDropStart;
+                return true;
+            }
+            Action::HardDrop => {
+                self.hard_drop();
+                return true;
+            }
+            Action::Hold => return self.hold(),
+        };
+
+        if moved {
+            self.extend_lock_delay();
+            self.events.push(GameEvent::Moved);
+        }
+        moved
+    }
+
+    /// Advances the game by one fixed step and applies gravity.
+    pub fn tick(&mut self) {
+        if self.is_over() || self.phase == Phase::Ready {
+            return;
+        }
+
+        if self.phase == Phase::Countdown {
+            self.delay = self.delay.saturating_sub(1);
+            if self.delay == 0 {
+                self.resume();
+            }
+            return;
+        }
+
+        self.ticks += 1;
+
+        if self.phase == Phase::Falling {
+            self.fall();
+        } else {
+            self.delay = self.delay.saturating_sub(1);
+            if self.delay == 0 {
+                self.end_delay();
+            }
+        }
+
+        self.check_end();
+    }
+
+    /// Moves the active piece down by one row, or locks it at once if it cannot
+    /// move, ignoring the lock delay. Delays still take their time.
+    pub fn step(&mut self) {
+        if self.is_over() {
+            return;
+        }
+
+        if self.phase != Phase::Falling {
+            self.tick();
+            return;
+        }
+
+        if !self.try_move(self.piece.moved(0, -1)) {
+            self.lock();
+        }
+        self.check_end();
+    }
+
+    pub fn drain_events(&mut self) -> impl Iterator<Item = GameEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    fn fall(&mut self) {
+        self.gravity += self.rows_per_tick();
+
+        while self.gravity >= 1. {
+            self.gravity -= 1.;
+            if !self.try_move(self.piece.moved(0, -1)) {
+                self.gravity = 0.;
+                break;
+            }
+            if self.soft_drop {
+                self.scoring.soft_drop(1);
+            }
+        }
+
+        if self.board.collides(&self.piece.moved(0, -1)) {
+            self.lock_delay.landed = true;
+            self.lock_delay.ticks += 1;
+
+            if self.lock_delay.ticks >= to_ticks(self.config.lock_delay)
+                || self.lock_delay.resets >= self.config.lock_resets
+            {
+                self.lock();
+            }
+        }
+    }
+
+    fn rows_per_tick(&self) -> f32 {
+        // gravity is defined per frame at 60 frames per second
+        let rows_per_frame = self.config.gravity.rows_per_frame(self.scoring.level());
+        let rows_per_tick = rows_per_frame * 60. / TICKS_PER_SECOND as f32;
+
+        if self.soft_drop {
+            rows_per_tick * self.config.soft_drop_factor
+        } else {
+            rows_per_tick
+        }
+    }
+
+    fn try_move(&mut self, next: Piece) -> bool {
+        if self.board.collides(&next) {
+            return false;
+        }
+
+        self.piece = next;
+        self.last_kick = None;
+        if next.y < self.lock_delay.lowest_y {
+            self.lock_delay = LockDelay::new(next.y);
+        }
+        true
+    }
+
+    /// Moving or rotating a piece that has landed restarts the lock delay, up
+    /// to a limit of resets per row reached.
+    fn extend_lock_delay(&mut self) {
+        if self.lock_delay.landed && self.lock_delay.resets < self.config.lock_resets {
+            self.lock_delay.ticks = 0;
+            self.lock_delay.resets += 1;
+        }
+    }
+
+    fn move_to_wall(&mut self, dx: i8) -> bool {
+        let mut moved = false;
+        while self.try_move(self.piece.moved(dx, 0)) {
+            moved = true;
+        }
+        moved
+    }
+
+    fn try_rotate(&mut self, turn: Turn) -> bool {
+        let rotated = self.piece.rotated(turn);
+        let kick = self
+            .piece
+            .kicks(turn)
+            .iter()
+            .position(|&(dx, dy)| self.try_move(rotated.moved(dx, dy)));
+
+        if kick.is_some() {
+            self.last_kick = kick;
+        }
+        kick.is_some()
+    }
+
+    /// Three-corner rule: a T that was rotated into place with at least three
+    /// of its diagonal neighbours occupied. It is a mini T-spin unless both
+    /// corners it points to are occupied or it took the last wall kick.
+    fn spin(&self) -> Spin {
+        let Some(kick) = self.last_kick else {
+            return Spin::None;
+        };
+        if self.piece.tetromino != Tetromino::T {
+            return Spin::None;
+        }
+
+        let (x, y) = (self.piece.x, self.piece.y);
+        let occupied = |(dx, dy): (i8, i8)| self.board.is_occupied(x + dx, y + dy);
+        let corners = [(-1, 1), (1, 1), (1, -1), (-1, -1)];
+        if corners
+            .into_iter()
+            .filter(|&corner| occupied(corner))
+            .count()
+            < 3
+        {
+            return Spin::None;
+        }
+
+        let front = match self.piece.rotation {
+            Rotation::Spawn => [(-1, 1), (1, 1)],
+            Rotation::Right => [(1, 1), (1, -1)],
+            Rotation::Flip => [(1, -1), (-1, -1)],
+            Rotation::Left => [(-1, -1), (-1, 1)],
+        };
+        if front.into_iter().all(occupied) || kick == 4 {
+            Spin::TSpin
+        } else {
+            Spin::Mini
+        }
+    }
+
+    fn hard_drop(&mut self) {
+        let ghost = self.ghost();
+        let rows = (self.piece.y - ghost.y) as u8;
+        if rows > 0 {
+            self.last_kick = None;
+        }
+        self.piece = ghost;
+        self.scoring.hard_drop(rows);
+
+        self.events.push(GameEvent::HardDropped(rows));
+        self.lock();
+        // stop the clock right away when the drop reached the goal
+        self.check_end();
+    }
+
+    fn hold(&mut self) -> bool {
+        if self.hold_used {
+            return false;
+        }
+
+        let next = match self.held.replace(self.piece.tetromino) {
+            Some(held) => held,
+            None => self.next_tetromino(),
+        };
+
+        self.hold_used = true;
+        self.events.push(GameEvent::Held);
+        self.spawn(next);
+        true
+    }
+
+    fn lock(&mut self) {
+        let visible = self.config.height;
+        let hidden = self.piece.cells().filter(|&(_, y)| y >= visible).count();
+        if hidden == 4 || self.piece.cells().any(|(_, y)| y >= self.board.height()) {
+            self.end(Outcome::ToppedOut(TopOut::LockOut));
+            return;
+        }
+        if hidden > 0 && self.config.partial_lock_out {
+            self.end(Outcome::ToppedOut(TopOut::PartialLockOut));
+            return;
+        }
+
+        let spin = self.spin();
+        self.board.place(&self.piece);
+        self.events.push(GameEvent::Locked);
+
+        let full_rows = self.board.full_rows();
+        let lines = full_rows.len() as u8;
+        self.garbage_cleared += full_rows
+            .iter()
+            .filter(|&&y| self.board.has_garbage(y))
+            .count() as u32;
+        let mut clear = LineClear {
+            lines,
+            spin,
+            perfect_clear: lines > 0
+                && self.board.cells().all(|((_, y), _)| full_rows.contains(&y)),
+            ..LineClear::default()
+        };
+        clear.back_to_back = clear.is_difficult() && self.scoring.back_to_back();
+        self.scoring.lock(&clear);
+        clear.combo = self.scoring.combo().unwrap_or(0);
+
+        if lines > 0 || spin != Spin::None {
+            self.events.push(GameEvent::Cleared(clear));
+        }
+
+        self.hold_used = false;
+        self.pieces += 1;
+
+        self.delay = to_ticks(self.config.line_clear_delay);
+        if lines > 0 && self.delay > 0 {
+            self.phase = Phase::LineClear;
+        } else {
+            self.board.clear_lines();
+            self.start_entry();
+        }
+    }
+
+    fn start_entry(&mut self) {
+        self.delay = to_ticks(self.config.entry_delay);
+        if self.delay > 0 {
+            self.phase = Phase::Entry;
+        } else {
+            self.spawn_next();
+        }
+    }
+
+    fn end_delay(&mut self) {
+        match self.phase {
+            Phase::LineClear => {
+                self.board.clear_lines();
+                self.start_entry();
+            }
+            Phase::Entry => self.spawn_next(),
+            Phase::Countdown => self.resume(),
+            Phase::Falling | Phase::Ready => (),
+        }
+    }
+
+    fn spawn_next(&mut self) {
+        self.events.push(GameEvent::Spawned);
+
+        let next = self.next_tetromino();
+        self.spawn(next);
+        self.resume();
+    }
+
+    /// Lets the piece fall and applies the inputs buffered during a delay.
+    fn resume(&mut self) {
+        self.phase = Phase::Falling;
+
+        for action in std::mem::take(&mut self.buffered) {
+            self.apply(action);
+        }
+    }
+
+    fn next_tetromino(&mut self) -> Tetromino {
+        let next = self.queue.pop_front().unwrap();
+        self.queue.push_back(self.randomizer.next());
+        next
+    }
+
+    /// Pieces spawn just above the visible rows and drop by one row at once if
+    /// nothing is in the way.
+    fn spawn(&mut self, tetromino: Tetromino) {
+        self.piece = Piece::new(
+            tetromino,
+            spawn_column(self.config.width),
+            self.config.height,
+        );
+        self.last_kick = None;
+        self.gravity = 0.;
+
+        if self.board.collides(&self.piece) {
+            self.end(Outcome::ToppedOut(TopOut::BlockOut));
+            return;
+        }
+
+        let dropped = self.piece.moved(0, -1);
+        if !self.board.collides(&dropped) {
+            self.piece = dropped;
+        }
+        self.lock_delay = LockDelay::new(self.piece.y);
+    }
+
+    fn end(&mut self, outcome: Outcome) {
+        self.outcome = Some(outcome);
+        self.events.push(GameEvent::GameOver);
+    }
+
+    fn check_end(&mut self) {
+        if self.is_over() {
+            return;
+        }
+
+        if let Some(outcome) = self.mode.end(self) {
+            self.end(outcome);
+        }
+    }
+}
+
+/// Pieces spawn centered, or rounded to the left on even widths.
+fn spawn_column(width: i8) -> i8 {
+    (width - 1) / 2
+}
+
+fn to_ticks(duration: Duration) -> u32 {
+    (duration.as_secs_f32() * TICKS_PER_SECOND as f32).round() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::{Action, Game, GameEvent, Phase, Spin, TopOut};
+    use crate::{
+        Cell, Config, Dig, GameMode, Gravity, LineClear, Marathon, Outcome, Piece, Record,
+        Rotation, Sprint, Start, Tetromino, Turn,
+    };
+
+    const CELL: Cell = Cell::Piece(Tetromino::O);
+
+    #[test]
+    fn piece_falls_and_locks() {
+        let mut game = Game::new(Config::default(), 0);
+
+        while !game.drain_events().any(|event| event == GameEvent::Spawned) {
+            game.step();
+        }
+
+        assert_eq!(game.board().cells().count(), 4);
+        assert!(game.board().cells().any(|((_, y), _)| y == 0));
+    }
+
+    #[test]
+    fn hard_drop_locks_at_once() {
+        let mut game = Game::new(Config::default(), 0);
+        let tetromino = game.piece().tetromino;
+
+        assert!(game.apply(Action::HardDrop));
+        assert_eq!(game.board().cells().count(), 4);
+        assert!(game
+            .board()
+            .cells()
+            .all(|(_, cell)| cell == Cell::Piece(tetromino)));
+        assert!(game.board().cells().any(|((_, y), _)| y == 0));
+
+        let events: Vec<_> = game.drain_events().collect();
+        assert!(matches!(events[0], GameEvent::HardDropped(rows) if rows > 0));
+        assert_eq!(events[1..], [GameEvent::Locked, GameEvent::Spawned]);
+    }
+
+    #[test]
+    fn clearing_lines_scores() {
+        let mut game = Game::new(Config::default(), 0);
+        for x in game.board().columns() {
+            if x != 0 {
+                game.board.set(x, 0, Some(CELL));
+            }
+        }
+        game.piece = Piece::new(Tetromino::I, 0, 5).rotated(Turn::Left);
+
+        game.apply(Action::HardDrop);
+        assert_eq!(game.scoring().lines(), 1);
+        // dropped three rows, then cleared a single
+        assert_eq!(game.scoring().score(), 2 * 3 + 100);
+    }
+
+    #[test]
+    fn t_spin_double() {
+        let mut game = Game::new(Config::default(), 0);
+        // a T slot in the two bottom rows, with an overhang on the left
+        for x in game.board().columns() {
+            game.board.set(x, 0, Some(CELL));
+            if !(3..=5).contains(&x) {
+                game.board.set(x, 1, Some(CELL));
+            }
+        }
+        game.board.set(4, 0, None);
+        game.board.set(3, 2, Some(CELL));
+        game.piece = Piece::new(Tetromino::T, 4, 1).rotated(Turn::Right);
+
+        assert!(game.apply(Action::RotateRight));
+        game.apply(Action::HardDrop);
+
+        let clear = game.drain_events().find_map(|event| match event {
+            GameEvent::Cleared(clear) => Some(clear),
+            _ => None,
+        });
+        assert_eq!(
+            clear.map(|clear| (clear.lines, clear.spin)),
+            Some((2, Spin::TSpin))
+        );
+    }
+
+    #[test]
+    fn perfect_clear() {
+        let mut game = Game::new(Config::default(), 0);
+        set_up_single(&mut game);
+
+        game.apply(Action::HardDrop);
+        assert!(game.drain_events().any(|event| matches!(
+            event,
+            GameEvent::Cleared(clear) if clear.perfect_clear
+        )));
+        assert_eq!(game.board().cells().count(), 0);
+    }
+
+    #[test]
+    fn ghost_lands_on_stack() {
+        let mut game = Game::new(Config::default(), 0);
+        for x in game.board().columns() {
+            game.board.set(x, 0, Some(CELL));
+            game.board.set(x, 1, Some(CELL));
+        }
+        game.piece = Piece::new(Tetromino::T, 4, 10);
+
+        assert_eq!(game.ghost().cells().map(|(_, y)| y).min(), Some(2));
+        assert_eq!(game.piece().y, 10);
+    }
+
+    #[test]
+    fn hold_once_per_piece() {
+        let mut game = Game::new(Config::default(), 0);
+        let first = game.piece().tetromino;
+
+        assert!(game.apply(Action::Hold));
+        assert_eq!(game.held(), Some(first));
+        assert!(!game.apply(Action::Hold));
+
+        game.apply(Action::HardDrop);
+        assert!(game.can_hold());
+
+        let third = game.piece().tetromino;
+        assert!(game.apply(Action::Hold));
+        assert_eq!(game.piece().tetromino, first);
+        assert_eq!(game.held(), Some(third));
+    }
+
+    #[test]
+    fn queue_reveals_upcoming_pieces() {
+        let mut game = Game::new(
+            Config {
+                preview: 3,
+                ..Config::default()
+            },
+            0,
+        );
+        let queue: Vec<_> = game.next_pieces().collect();
+        assert_eq!(queue.len(), 3);
+
+        game.apply(Action::HardDrop);
+        assert_eq!(game.piece().tetromino, queue[0]);
+        assert_eq!(game.next_pieces().take(2).collect::<Vec<_>>(), queue[1..]);
+        assert_eq!(game.next_pieces().count(), 3);
+    }
+
+    fn landed_game(config: Config) -> Game {
+        let mut game = Game::new(config, 0);
+        game.piece = Piece::new(Tetromino::T, 4, 0);
+        game.lock_delay.lowest_y = 0;
+        game
+    }
+
+    /// Fills the bottom row except for a gap that the I piece above it clears
+    /// when hard dropped.
+    fn set_up_single(game: &mut Game) {
+        for x in game.board().columns() {
+            if !(3..=6).contains(&x) {
+                game.board.set(x, 0, Some(CELL));
+            }
+        }
+        game.piece = Piece::new(Tetromino::I, 4, 5);
+    }
+
+    #[test]
+    fn lock_delay_on_stack() {
+        let mut game = landed_game(Config {
+            lock_delay: Duration::from_millis(500),
+            ..Config::default()
+        });
+
+        for _ in 0..29 {
+            game.tick();
+        }
+        assert_eq!(game.board().cells().count(), 0);
+
+        game.tick();
+        assert_eq!(game.board().cells().count(), 4);
+    }
+
+    #[test]
+    fn moves_reset_lock_delay() {
+        let mut game = landed_game(Config {
+            lock_delay: Duration::from_millis(500),
+            lock_resets: 3,
+            ..Config::default()
+        });
+        let actions = [Action::Left, Action::Right, Action::Left];
+
+        for action in actions {
+            for _ in 0..20 {
+                game.tick();
+            }
+            assert!(game.apply(action));
+        }
+        assert_eq!(game.board().cells().count(), 0);
+
+        // the resets are used up, so the piece locks on the next step
+        game.apply(Action::Right);
+        game.tick();
+        assert_eq!(game.board().cells().count(), 4);
+    }
+
+    #[test]
+    fn move_to_wall() {
+        let mut game = Game::new(Config::default(), 0);
+        let right = game.board().columns().end - 1;
+
+        assert!(game.apply(Action::RightToWall));
+        assert_eq!(game.piece().cells().map(|(x, _)| x).max(), Some(right));
+        assert!(game.drain_events().eq([GameEvent::Moved]));
+
+        // held against the wall, nothing moves and nothing is reported
+        assert!(!game.apply(Action::RightToWall));
+        assert_eq!(game.drain_events().count(), 0);
+    }
+
+    #[test]
+    fn twenty_g_drops_at_once() {
+        let mut game = Game::new(
+            Config {
+                gravity: Gravity::Custom(vec![20.]),
+                ..Config::default()
+            },
+            0,
+        );
+
+        game.tick();
+        assert_eq!(game.piece(), &game.ghost());
+        assert_eq!(game.board().cells().count(), 0);
+    }
+
+    #[test]
+    fn spawns_above_visible_rows() {
+        let game = Game::new(Config::default(), 0);
+
+        assert_eq!(game.board().height(), 40);
+        assert_eq!(game.piece().y, 19);
+    }
+
+    #[test]
+    fn marathon_ends_on_last_level() {
+        let marathon = Marathon {
+            start_level: 14,
+            endless: false,
+        };
+        let mut game = Game::with_mode(Config::default(), 0, Arc::new(marathon));
+        let tetris = LineClear {
+            lines: 4,
+            ..LineClear::default()
+        };
+        for _ in 0..5 {
+            game.scoring.lock(&tetris);
+        }
+        game.check_end();
+
+        assert_eq!(game.outcome(), Some(Outcome::Completed));
+        assert_eq!(game.scoring().level(), 16);
+        let summary = game.summary().unwrap();
+        assert_eq!(summary.entries[1], ("Level", "15".to_string()));
+    }
+
+    #[test]
+    fn dig_ends_when_garbage_is_cleared() {
+        let dig = Dig {
+            rows: 1,
+            messiness: 0.,
+            start: Start::Immediately,
+        };
+        let mut game = Game::with_mode(Config::default(), 0, Arc::new(dig));
+        assert!(game.board().has_garbage(0));
+
+        // move the hole to a well for an I piece at the left wall
+        for x in game.board().columns() {
+            let cell = (x >= 4).then_some(Cell::Garbage);
+            game.board.set(x, 0, cell);
+        }
+        game.piece = Piece::new(Tetromino::I, 3, 5);
+        game.apply(Action::LeftToWall);
+        game.apply(Action::HardDrop);
+
+        assert_eq!(game.garbage_cleared(), 1);
+        assert_eq!(game.outcome(), Some(Outcome::Completed));
+    }
+
+    #[test]
+    fn garbage_lifts_piece_and_tops_out() {
+        let mut game = Game::new(Config::default(), 0);
+        let height = game.board().height() as u32;
+        game.piece = Piece::new(Tetromino::O, 4, 0);
+
+        game.add_garbage_row(&[0]);
+        assert_eq!(game.piece().cells().map(|(_, y)| y).min(), Some(1));
+
+        game.add_garbage(height, 0.5);
+        assert_eq!(game.outcome(), Some(Outcome::ToppedOut(TopOut::GarbageOut)));
+    }
+
+    #[test]
+    fn block_out() {
+        let mut game = Game::new(Config::default(), 0);
+        game.board.set(4, 20, Some(CELL));
+        game.apply(Action::HardDrop);
+
+        assert_eq!(game.top_out(), Some(TopOut::BlockOut));
+    }
+
+    #[test]
+    fn lock_out() {
+        let lock_above = |partial_lock_out, y| {
+            let mut game = Game::new(
+                Config {
+                    partial_lock_out,
+                    ..Config::default()
+                },
+                0,
+            );
+            for x in game.board().columns() {
+                for y in 0..y {
+                    if x != 0 {
+                        game.board.set(x, y, Some(CELL));
+                    }
+                }
+            }
+            game.piece = Piece::new(Tetromino::O, 7, y);
+            game.step();
+            game.top_out()
+        };
+
+        assert_eq!(lock_above(false, 20), Some(TopOut::LockOut));
+        assert_eq!(lock_above(false, 19), None);
+        assert_eq!(lock_above(true, 19), Some(TopOut::PartialLockOut));
+    }
+
+    #[test]
+    fn delays_buffer_inputs() {
+        let mut game = Game::new(
+            Config {
+                entry_delay: Duration::from_millis(100),
+                line_clear_delay: Duration::from_millis(400),
+                ..Config::default()
+            },
+            0,
+        );
+        set_up_single(&mut game);
+
+        game.apply(Action::HardDrop);
+        assert_eq!(game.phase(), Phase::LineClear);
+        assert_eq!(game.clearing_rows(), vec![0]);
+        assert!(game.apply(Action::LeftToWall));
+
+        for _ in 0..24 {
+            game.tick();
+        }
+        assert_eq!(game.phase(), Phase::Entry);
+        assert_eq!(game.board().cells().count(), 0);
+
+        for _ in 0..6 {
+            game.tick();
+        }
+        assert_eq!(game.phase(), Phase::Falling);
+        assert_eq!(game.piece().cells().map(|(x, _)| x).min(), Some(0));
+    }
+
+    #[test]
+    fn clock_starts_on_first_input() {
+        let mut game = Game::new(
+            Config {
+                start: Start::FirstInput,
+                ..Config::default()
+            },
+            0,
+        );
+        let y = game.piece().y;
+
+        for _ in 0..100 {
+            game.tick();
+        }
+        assert_eq!((game.ticks(), game.piece().y), (0, y));
+
+        game.apply(Action::Left);
+        game.tick();
+        assert_eq!(game.ticks(), 1);
+    }
+
+    #[test]
+    fn countdown_buffers_inputs() {
+        let mut game = Game::new(
+            Config {
+                start: Start::Countdown(Duration::from_secs(1)),
+                ..Config::default()
+            },
+            0,
+        );
+        game.apply(Action::LeftToWall);
+
+        for _ in 0..59 {
+            game.tick();
+        }
+        assert!(game.countdown().is_some());
+
+        game.tick();
+        assert_eq!(game.countdown(), None);
+        assert_eq!(game.ticks(), 0);
+        assert_eq!(game.piece().cells().map(|(x, _)| x).min(), Some(0));
+    }
+
+    #[test]
+    fn sprint_stops_clock_on_last_clear() {
+        let sprint = Sprint {
+            lines: 1,
+            start: Start::Immediately,
+        };
+        let mut game = Game::with_mode(Config::default(), 0, Arc::new(sprint));
+        set_up_single(&mut game);
+        for _ in 0..30 {
+            game.tick();
+        }
+        assert_eq!(sprint.record(&game), None);
+
+        game.apply(Action::HardDrop);
+
+        assert_eq!(game.outcome(), Some(Outcome::Completed));
+        assert_eq!(
+            sprint.record(&game),
+            Some(Record::Time(Duration::from_millis(500)))
+        );
+    }
+
+    #[test]
+    fn stacking_ends_game() {
+        let mut game = Game::new(Config::default(), 0);
+
+        for _ in 0..1000 {
+            game.step();
+        }
+
+        assert!(game.is_over());
+        assert!(game
+            .drain_events()
+            .any(|event| event == GameEvent::GameOver));
+    }
+
+    #[test]
+    fn rotation_kicks_off_wall() {
+        let mut game = Game::new(Config::default(), 0);
+        let left = game.board().columns().start;
+        // vertical I piece in the column next to its center, flush with the wall
+        game.piece = Piece::new(Tetromino::I, left - 1, 10).rotated(Turn::Right);
+
+        assert!(game.apply(Action::RotateRight));
+        assert_eq!(game.piece().rotation, Rotation::Flip);
+        assert_eq!(game.piece().cells().map(|(x, _)| x).min(), Some(left));
+    }
+
+    #[test]
+    fn rotation_fails_without_room() {
+        let mut game = Game::new(Config::default(), 0);
+        for y in 0..20 {
+            for x in game.board().columns() {
+                if x != 0 {
+                    game.board.set(x, y, Some(CELL));
+                }
+            }
+        }
+        game.piece = Piece::new(Tetromino::I, 0, 5).rotated(Turn::Left);
+
+        assert!(!game.apply(Action::RotateRight));
+        assert_eq!(game.piece().rotation, Rotation::Left);
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let play = |seed| {
+            let mut game = Game::new(Config::default(), seed);
+            let actions = [Action::Left, Action::RotateRight, Action::Right];
+
+            for tick in 0..10_000 {
+                if tick % 7 == 0 {
+                    game.apply(actions[tick / 7 % actions.len()]);
+                }
+                game.tick();
+            }
+
+            game.board().cells().collect::<Vec<_>>()
+        };
+
+        assert_eq!(play(1), play(1));
+        assert_ne!(play(1), play(2));
+    }
+}
@@ -0,0 +1,64 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+/// Mixed into the game seed, any non-zero value gives a different seed.
+const GARBAGE_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Picks the holes of garbage rows, so they follow from the seed of the game
+/// like the pieces do.
+#[derive(Debug, Clone)]
+pub struct Garbage {
+    rng: StdRng,
+    hole: Option<i8>,
+}
+
+impl Garbage {
+    pub fn new(seed: u64) -> Self {
+        // flipping bits keeps this stream apart from the randomizer, which is
+        // seeded with the same value
+        Self {
+            rng: StdRng::seed_from_u64(seed ^ GARBAGE_SEED),
+            hole: None,
+        }
+    }
+
+    /// The hole of the next row. `messiness` is the chance, from 0 to 1, that
+    /// the hole moves away from the column of the row below: 0 digs a single
+    /// well, 1 a new column on every row.
+    pub fn next_hole(&mut self, width: i8, messiness: f32) -> i8 {
+        let hole = match self.hole {
+            Some(hole) if width > 1 && self.rng.gen_bool(messiness.clamp(0., 1.) as f64) => {
+                let other = self.rng.gen_range(0..width - 1);
+                if other >= hole {
+                    other + 1
+                } else {
+                    other
+                }
+            }
+            Some(hole) => hole,
+            None => self.rng.gen_range(0..width),
+        };
+
+        self.hole = Some(hole);
+        hole
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Garbage;
+
+    #[test]
+    fn messiness() {
+        let mut garbage = Garbage::new(0);
+        let first = garbage.next_hole(10, 0.);
+        assert!((0..10).all(|_| garbage.next_hole(10, 0.) == first));
+
+        let mut hole = garbage.next_hole(10, 1.);
+        for _ in 0..10 {
+            let next = garbage.next_hole(10, 1.);
+            assert_ne!(next, hole);
+            assert!((0..10).contains(&next));
+            hole = next;
+        }
+    }
+}
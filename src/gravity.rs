@@ -0,0 +1,75 @@
+/// Frames per row on the NES, from level 0 on.
+const NES_FRAMES_PER_ROW: [u8; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    1,
+];
+
+/// Speed at which pieces fall, by level. Speeds are given in G, rows per
+/// frame at 60 frames per second, so 20G drops a piece to the stack at once.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Gravity {
+    /// The guideline curve, reaching 20G around level 19.
+    #[default]
+    Guideline,
+    /// The NES speeds, with our level 1 being the NES level 0.
+    Nes,
+    /// Speed in G for each level starting from 1. The last one holds for all
+    /// higher levels.
+    Custom(Vec<f32>),
+}
+
+impl Gravity {
+    /// Rows per frame at the given level.
+    pub fn rows_per_frame(&self, level: u32) -> f32 {
+        let index = level.max(1) as usize - 1;
+
+        match self {
+            Gravity::Guideline => {
+                let level = index as f32;
+                let seconds_per_row = (0.8 - level * 0.007).powf(level);
+                (1. / (seconds_per_row * 60.)).min(20.)
+            }
+            Gravity::Nes => {
+                let frames = NES_FRAMES_PER_ROW[index.min(NES_FRAMES_PER_ROW.len() - 1)];
+                1. / frames as f32
+            }
+            Gravity::Custom(table) => table
+                .get(index)
+                .or_else(|| table.last())
+                .copied()
+                .unwrap_or(0.),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Gravity;
+
+    #[test]
+    fn guideline_speeds_up_to_20g() {
+        assert_eq!(Gravity::Guideline.rows_per_frame(1), 1. / 60.);
+
+        let speeds: Vec<_> = (1..=30)
+            .map(|level| Gravity::Guideline.rows_per_frame(level))
+            .collect();
+        assert!(speeds.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(speeds[19], 20.);
+    }
+
+    #[test]
+    fn nes_table() {
+        assert_eq!(Gravity::Nes.rows_per_frame(1), 1. / 48.);
+        assert_eq!(Gravity::Nes.rows_per_frame(30), 1.);
+        assert_eq!(Gravity::Nes.rows_per_frame(99), 1.);
+    }
+
+    #[test]
+    fn custom_table_holds_last_speed() {
+        let gravity = Gravity::Custom(vec![0.5, 2., 20.]);
+
+        assert_eq!(gravity.rows_per_frame(2), 2.);
+        assert_eq!(gravity.rows_per_frame(10), 20.);
+        assert_eq!(Gravity::Custom(Vec::new()).rows_per_frame(1), 0.);
+    }
+}
@@ -0,0 +1,26 @@
+//! Headless Tetris rules engine.
+//!
+//! Nothing in here depends on Bevy, so the rules can be unit-tested, simulated
+//! or driven by a bot without opening a window.
+
+mod board;
+mod config;
+mod game;
+mod garbage;
+mod gravity;
+mod mode;
+mod piece;
+mod randomizer;
+mod scoring;
+
+pub use board::{Board, Cell};
+pub use config::{Config, Start, MAX_PREVIEW};
+pub use game::{Action, Game, GameEvent, Phase, TopOut, TICKS_PER_SECOND};
+pub use garbage::Garbage;
+pub use gravity::Gravity;
+pub use mode::{
+    format_time, Dig, Endless, GameMode, Goal, Marathon, Outcome, Record, Sprint, Summary, Ultra,
+};
+pub use piece::{Piece, Rotation, Tetromino, Turn};
+pub use randomizer::{Bag, History, Random, Randomizer, RandomizerKind};
+pub use scoring::{LineClear, Scoring, Spin};
@@ -1,11 +1,15 @@
 use audio::SoundAssets;
+use bests::{PersonalBests, BESTS_PATH};
 use bevy::{core_pipeline::bloom::BloomSettings, prelude::*, window::PresentMode};
 use bevy_asset_loader::prelude::*;
-use bricks::LinesRemoved;
-use controls::ControlEvent;
+use bricks::LinesCleared;
+use controls::{ControlEvent, Handling};
 use shape::ShapeSpawned;
+use std::sync::Arc;
+use tetris::{Config, Dig, Endless, Game, GameMode, Marathon, Scoring, Sprint, Ultra};
 
 mod audio;
+mod bests;
 mod bricks;
 mod controls;
 mod shape;
@@ -15,13 +19,6 @@ mod ui;
 const BRICK_SIZE: f32 = 30.;
 const OFFSET_X: f32 = 0.;
 const OFFSET_Y: f32 = 0.;
-const BRICK_ROWS: i8 = 20;
-const BRICK_ROWS_RANGE: std::ops::Range<i8> = 0..BRICK_ROWS;
-const BRICK_COLS: i8 = 11;
-const BRICK_COLS_RANGE: std::ops::RangeInclusive<i8> = {
-    let half = (BRICK_COLS - 1) / 2;
-    -half..=half
-};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Default, States)]
 pub enum GameState {
@@ -33,6 +30,47 @@ pub enum GameState {
     GameOver,
 }
 
+#[derive(Resource, Deref, DerefMut)]
+pub struct Engine(Game);
+
+/// Score and level of the current game, kept in sync with the engine.
+#[derive(Resource, Deref)]
+pub struct Score(Scoring);
+
+#[derive(Resource)]
+pub struct Settings {
+    /// Rules of the next game, including the board dimensions.
+    pub game: Config,
+    pub mode: Arc<dyn GameMode>,
+    /// Fixed seed for reproducible sessions, a random one is used otherwise.
+    pub seed: Option<u64>,
+    /// Show where the active piece would land.
+    pub ghost: bool,
+    pub handling: Handling,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            game: Config::default(),
+            mode: Arc::new(Endless),
+            seed: None,
+            ghost: true,
+            handling: Handling::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// The rules of the next game, after the mode has adjusted them, to lay
+    /// out the board. The game applies the mode on its own.
+    pub fn config(&self) -> Config {
+        let mut config = self.game.clone();
+        self.mode.configure(&mut config);
+        config
+    }
+}
+
 #[derive(Resource, Default)]
 struct GameStats {
     lines_removed: LineStats,
@@ -49,6 +87,14 @@ impl LineStats {
 }
 
 fn main() {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let settings = Settings {
+        seed: take_seed(&mut args),
+        mode: mode_from(&args),
+        ..default()
+    };
+    let window_size = ui::window_size(&settings.config());
+
     App::new()
         .add_state::<GameState>()
         .add_loading_state(
@@ -58,7 +104,7 @@ fn main() {
         .insert_resource(Msaa::Sample2)
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
-                resolution: (800., 1000.).into(),
+                resolution: (window_size.x, window_size.y).into(),
                 resizable: false,
                 title: "Tetris".into(),
                 present_mode: PresentMode::Fifo,
@@ -73,15 +119,61 @@ fn main() {
         .add_plugin(audio::AudioPlugin)
         .add_plugin(controls::ControlsPlugin)
         .add_plugin(tick::TickPlugin)
+        .insert_resource(settings)
         .init_resource::<GameStats>()
+        .insert_resource(PersonalBests::load(BESTS_PATH))
         .add_startup_system(setup)
         .add_system(bevy::window::close_on_esc)
         .add_system(pause_resume_game)
         .add_system(reset.in_schedule(OnEnter(GameState::Starting)))
         .add_system(update_statistics.in_set(OnUpdate(GameState::InGame)))
+        .add_system(update_score.run_if(resource_exists::<Engine>()))
         .run();
 }
 
+/// Removes `--seed <number>` from the arguments, to replay a game or play the
+/// same pieces as someone else.
+fn take_seed(args: &mut Vec<String>) -> Option<u64> {
+    let index = args.iter().position(|arg| arg == "--seed")?;
+    args.remove(index);
+    (index < args.len())
+        .then(|| args.remove(index))
+        .and_then(|seed| seed.parse().ok())
+}
+
+/// The mode is picked by name on the command line, like `tetris sprint`.
+/// Marathon takes the start level as a second argument, Dig the number of
+/// garbage rows and then the messiness, like `tetris dig 10 0.3`.
+fn mode_from(args: &[String]) -> Arc<dyn GameMode> {
+    let number = args.get(1).and_then(|number| number.parse().ok());
+    let start_level = number.unwrap_or(1);
+
+    match args.first().map(String::as_str) {
+        Some("marathon") => Arc::new(Marathon {
+            start_level,
+            endless: false,
+        }),
+        Some("marathon-endless") => Arc::new(Marathon {
+            start_level,
+            endless: true,
+        }),
+        Some("dig") => {
+            let dig = Dig::default();
+            Arc::new(Dig {
+                rows: number.unwrap_or(dig.rows),
+                messiness: args
+                    .get(2)
+                    .and_then(|messiness| messiness.parse().ok())
+                    .unwrap_or(dig.messiness),
+                ..dig
+            })
+        }
+        Some("sprint") => Arc::new(Sprint::default()),
+        Some("ultra") => Arc::new(Ultra::default()),
+        _ => Arc::new(Endless),
+    }
+}
+
 fn setup(mut commands: Commands) {
     commands.spawn((
         Camera2dBundle {
@@ -120,18 +212,62 @@ fn pause_resume_game(
 fn update_statistics(
     mut stats: ResMut<GameStats>,
     mut shapes: EventReader<ShapeSpawned>,
-    mut lines: EventReader<LinesRemoved>,
+    mut clears: EventReader<LinesCleared>,
 ) {
     for _ in shapes.iter() {
         stats.shapes_spawned += 1;
     }
 
-    for event in lines.iter() {
-        stats.lines_removed.add(**event as usize);
+    for clear in clears.iter().filter(|clear| clear.lines > 0) {
+        stats.lines_removed.add(clear.lines as usize);
     }
 }
 
-fn reset(mut commands: Commands, mut next_state: ResMut<NextState<GameState>>) {
+fn update_score(engine: Res<Engine>, mut score: ResMut<Score>) {
+    if engine.is_changed() && engine.scoring() != &score.0 {
+        score.0 = engine.scoring().clone();
+    }
+}
+
+fn reset(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let seed = settings.seed.unwrap_or_else(rand::random);
+    info!("seed: {seed}");
+
+    let game = Game::with_mode(settings.game.clone(), seed, settings.mode.clone());
+
     commands.insert_resource(GameStats::default());
+    commands.insert_resource(Score(game.scoring().clone()));
+    commands.insert_resource(Engine(game));
     next_state.set(GameState::InGame);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{mode_from, take_seed};
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn seed_argument() {
+        let mut sprint = args("sprint --seed 42");
+        assert_eq!(take_seed(&mut sprint), Some(42));
+        assert_eq!(sprint, args("sprint"));
+        assert_eq!(mode_from(&sprint).name(), "Sprint");
+
+        let mut marathon = args("--seed 7 marathon 5");
+        assert_eq!(take_seed(&mut marathon), Some(7));
+        assert_eq!(mode_from(&marathon).key(), "marathon-5");
+
+        assert_eq!(mode_from(&args("dig 8 0.25")).key(), "dig-8-0.25");
+        assert_eq!(mode_from(&args("dig")).key(), "dig-10-0.5");
+
+        assert_eq!(take_seed(&mut args("ultra")), None);
+        assert_eq!(take_seed(&mut args("ultra --seed")), None);
+    }
+}
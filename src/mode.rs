@@ -0,0 +1,484 @@
+use std::{
+    fmt::{self, Debug, Display},
+    time::Duration,
+};
+
+use crate::{
+    config::{Config, Start},
+    game::{Game, TopOut},
+    scoring::LINES_PER_LEVEL,
+};
+
+/// Marathon games finish once this level is complete.
+const LAST_LEVEL: u32 = 15;
+
+/// What a mode asks the player to reach.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    Lines(u32),
+    /// Play for this long, the game ends when the time is up.
+    Time(Duration),
+    Score(u64),
+    /// Clear this many rows of garbage.
+    Garbage(u32),
+}
+
+/// How a game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The goal of the mode was reached.
+    Completed,
+    TimeUp,
+    ToppedOut(TopOut),
+}
+
+/// Results of a finished game, ready to be shown to the player.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub outcome: Outcome,
+    /// Labelled results in display order.
+    pub entries: Vec<(&'static str, String)>,
+}
+
+/// A result to compare against earlier games of the same mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Record {
+    /// Lower is better.
+    Time(Duration),
+    /// Higher is better.
+    Score(u64),
+}
+
+impl Record {
+    pub fn beats(&self, other: &Record) -> bool {
+        match (self, other) {
+            (Record::Time(time), Record::Time(other)) => time < other,
+            (Record::Score(score), Record::Score(other)) => score > other,
+            _ => true,
+        }
+    }
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Record::Time(time) => f.write_str(&format_time(*time)),
+            Record::Score(score) => write!(f, "{score}"),
+        }
+    }
+}
+
+/// Rules on top of the shared engine: how a game starts, what the player
+/// aims for and when it is over. Modes hold no state of their own, everything
+/// they need is read from the game.
+pub trait GameMode: Debug + Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Identifies the mode and its parameters, so records are only compared
+    /// with games played under the same rules.
+    fn key(&self) -> String {
+        self.name().to_lowercase().replace(' ', "-")
+    }
+
+    /// Sets the start conditions, such as the level and gravity curve.
+    fn configure(&self, _config: &mut Config) {}
+
+    /// Prepares the board once the first piece has spawned, like adding
+    /// garbage.
+    fn setup(&self, _game: &mut Game) {}
+
+    fn goal(&self) -> Option<Goal> {
+        None
+    }
+
+    /// Checked after every step. Topping out always ends the game.
+    fn end(&self, game: &Game) -> Option<Outcome> {
+        let scoring = game.scoring();
+
+        match self.goal()? {
+            Goal::Lines(lines) => (scoring.lines() >= lines).then_some(Outcome::Completed),
+            Goal::Time(time) => (game.time() >= time).then_some(Outcome::TimeUp),
+            Goal::Score(score) => (scoring.score() >= score).then_some(Outcome::Completed),
+            Goal::Garbage(rows) => (game.garbage_cleared() >= rows).then_some(Outcome::Completed),
+        }
+    }
+
+    /// The result to keep as a personal best, if the game counts as one.
+    fn record(&self, _game: &Game) -> Option<Record> {
+        None
+    }
+
+    fn summary(&self, game: &Game, outcome: Outcome) -> Summary {
+        let scoring = game.scoring();
+
+        Summary {
+            outcome,
+            entries: vec![
+                ("Score", scoring.score().to_string()),
+                ("Level", scoring.level().to_string()),
+                ("Lines", scoring.lines().to_string()),
+                ("Time", format_time(game.time())),
+            ],
+        }
+    }
+}
+
+/// Plays on until the stack tops out.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Endless;
+
+impl GameMode for Endless {
+    fn name(&self) -> &'static str {
+        "Endless"
+    }
+}
+
+/// Level up every ten lines until the last level is complete, or for as
+/// long as the player survives in the endless variant.
+#[derive(Debug, Clone, Copy)]
+pub struct Marathon {
+    pub start_level: u32,
+    pub endless: bool,
+}
+
+impl Default for Marathon {
+    fn default() -> Self {
+        Self {
+            start_level: 1,
+            endless: false,
+        }
+    }
+}
+
+impl GameMode for Marathon {
+    fn name(&self) -> &'static str {
+        if self.endless {
+            "Marathon Endless"
+        } else {
+            "Marathon"
+        }
+    }
+
+    fn key(&self) -> String {
+        let variant = if self.endless {
+            "marathon-endless"
+        } else {
+            "marathon"
+        };
+        format!("{variant}-{}", self.start_level)
+    }
+
+    fn configure(&self, config: &mut Config) {
+        config.start_level = self.start_level;
+    }
+
+    fn goal(&self) -> Option<Goal> {
+        let levels = (LAST_LEVEL + 1)
+            .saturating_sub(self.start_level.max(1))
+            .max(1);
+        (!self.endless).then_some(Goal::Lines(levels * LINES_PER_LEVEL))
+    }
+
+    fn summary(&self, game: &Game, outcome: Outcome) -> Summary {
+        let scoring = game.scoring();
+        // the lines that finish the last level would count as the next one
+        let level = match self.endless {
+            true => scoring.level(),
+            false => scoring.level().min(LAST_LEVEL.max(self.start_level)),
+        };
+
+        Summary {
+            outcome,
+            entries: vec![
+                ("Score", scoring.score().to_string()),
+                ("Level", level.to_string()),
+                ("Lines", scoring.lines().to_string()),
+                ("Time", format_time(game.time())),
+            ],
+        }
+    }
+
+    fn record(&self, game: &Game) -> Option<Record> {
+        (self.endless || game.outcome() == Some(Outcome::Completed))
+            .then(|| Record::Score(game.scoring().score()))
+    }
+}
+
+/// Clear a number of lines as fast as possible.
+#[derive(Debug, Clone, Copy)]
+pub struct Sprint {
+    pub lines: u32,
+    pub start: Start,
+}
+
+impl Default for Sprint {
+    fn default() -> Self {
+        Self {
+            lines: 40,
+            start: Start::Countdown(Duration::from_secs(3)),
+        }
+    }
+}
+
+impl GameMode for Sprint {
+    fn name(&self) -> &'static str {
+        "Sprint"
+    }
+
+    fn key(&self) -> String {
+        format!("sprint-{}", self.lines)
+    }
+
+    fn configure(&self, config: &mut Config) {
+        config.start = self.start;
+    }
+
+    fn goal(&self) -> Option<Goal> {
+        Some(Goal::Lines(self.lines))
+    }
+
+    fn record(&self, game: &Game) -> Option<Record> {
+        (game.outcome() == Some(Outcome::Completed)).then(|| Record::Time(game.time()))
+    }
+
+    fn summary(&self, game: &Game, outcome: Outcome) -> Summary {
+        Summary {
+            outcome,
+            entries: vec![
+                ("Time", format_time(game.time())),
+                ("Lines", game.scoring().lines().to_string()),
+                ("Pieces", game.pieces().to_string()),
+                ("PPS", format!("{:.2}", pieces_per_second(game))),
+            ],
+        }
+    }
+}
+
+/// Dig through rows of garbage as fast as possible. All rows are added at the
+/// start, more rows than the board holds end the game at once with
+/// `TopOut::GarbageOut`.
+#[derive(Debug, Clone, Copy)]
+pub struct Dig {
+    pub rows: u32,
+    /// Chance that the hole moves to another column from one row to the next.
+    pub messiness: f32,
+    pub start: Start,
+}
+
+impl Default for Dig {
+    fn default() -> Self {
+        Self {
+            rows: 10,
+            messiness: 0.5,
+            start: Start::Countdown(Duration::from_secs(3)),
+        }
+    }
+}
+
+impl GameMode for Dig {
+    fn name(&self) -> &'static str {
+        "Dig"
+    }
+
+    fn key(&self) -> String {
+        format!("dig-{}-{}", self.rows, self.messiness)
+    }
+
+    fn configure(&self, config: &mut Config) {
+        config.start = self.start;
+    }
+
+    fn setup(&self, game: &mut Game) {
+        game.add_garbage(self.rows, self.messiness);
+    }
+
+    fn goal(&self) -> Option<Goal> {
+        Some(Goal::Garbage(self.rows))
+    }
+
+    fn record(&self, game: &Game) -> Option<Record> {
+        (game.outcome() == Some(Outcome::Completed)).then(|| Record::Time(game.time()))
+    }
+
+    fn summary(&self, game: &Game, outcome: Outcome) -> Summary {
+        Summary {
+            outcome,
+            entries: vec![
+                ("Time", format_time(game.time())),
+                ("Garbage", game.garbage_cleared().to_string()),
+                ("Pieces", game.pieces().to_string()),
+                ("PPS", format!("{:.2}", pieces_per_second(game))),
+            ],
+        }
+    }
+}
+
+/// Score as many points as possible before the time runs out.
+#[derive(Debug, Clone, Copy)]
+pub struct Ultra {
+    pub duration: Duration,
+    pub start: Start,
+}
+
+impl Default for Ultra {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(120),
+            start: Start::Countdown(Duration::from_secs(3)),
+        }
+    }
+}
+
+impl GameMode for Ultra {
+    fn name(&self) -> &'static str {
+        "Ultra"
+    }
+
+    fn key(&self) -> String {
+        format!("ultra-{}", self.duration.as_millis())
+    }
+
+    fn configure(&self, config: &mut Config) {
+        config.start = self.start;
+    }
+
+    fn goal(&self) -> Option<Goal> {
+        Some(Goal::Time(self.duration))
+    }
+
+    fn record(&self, game: &Game) -> Option<Record> {
+        (game.outcome() == Some(Outcome::TimeUp)).then(|| Record::Score(game.scoring().score()))
+    }
+
+    fn summary(&self, game: &Game, outcome: Outcome) -> Summary {
+        let scoring = game.scoring();
+
+        Summary {
+            outcome,
+            entries: vec![
+                ("Score", scoring.score().to_string()),
+                ("Lines", scoring.lines().to_string()),
+                ("Pieces", game.pieces().to_string()),
+                ("Time", format_time(game.time())),
+            ],
+        }
+    }
+}
+
+fn pieces_per_second(game: &Game) -> f64 {
+    match game.time().as_secs_f64() {
+        seconds if seconds > 0. => game.pieces() as f64 / seconds,
+        _ => 0.,
+    }
+}
+
+/// Formats as minutes, seconds and milliseconds, like `1:05.250`.
+pub fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+
+    format!(
+        "{}:{:02}.{:03}",
+        millis / 60_000,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::{format_time, GameMode, Goal, Marathon, Outcome, Record, Ultra};
+    use crate::{Action, Config, Game, Start};
+
+    #[derive(Debug)]
+    struct Timed;
+
+    impl GameMode for Timed {
+        fn name(&self) -> &'static str {
+            "Timed"
+        }
+
+        fn configure(&self, config: &mut Config) {
+            config.start_level = 5;
+        }
+
+        fn goal(&self) -> Option<Goal> {
+            Some(Goal::Time(Duration::from_secs(1)))
+        }
+    }
+
+    #[test]
+    fn mode_sets_rules_and_ends_game() {
+        let mut game = Game::with_mode(Config::default(), 0, Arc::new(Timed));
+        assert_eq!(game.scoring().level(), 5);
+
+        for _ in 0..59 {
+            game.tick();
+        }
+        assert!(!game.is_over());
+
+        game.tick();
+        assert_eq!(game.outcome(), Some(Outcome::TimeUp));
+        assert!(!game.apply(Action::HardDrop));
+
+        let summary = game.summary().unwrap();
+        assert_eq!(summary.entries[3], ("Time", "0:01.000".to_string()));
+    }
+
+    #[test]
+    fn marathon_finishes_after_last_level() {
+        let marathon = Marathon::default();
+        assert_eq!(marathon.goal(), Some(Goal::Lines(150)));
+
+        let late_start = Marathon {
+            start_level: 10,
+            endless: false,
+        };
+        assert_eq!(late_start.goal(), Some(Goal::Lines(60)));
+
+        let endless = Marathon {
+            start_level: 10,
+            endless: true,
+        };
+        assert_eq!(endless.goal(), None);
+
+        let game = Game::with_mode(Config::default(), 0, Arc::new(late_start));
+        assert_eq!(game.scoring().level(), 10);
+    }
+
+    #[test]
+    fn ultra_scores_until_time_is_up() {
+        let ultra = Ultra {
+            duration: Duration::from_secs(2),
+            start: Start::Immediately,
+        };
+        let mut game = Game::with_mode(Config::default(), 0, Arc::new(ultra));
+        assert_eq!(game.time_left(), Some(Duration::from_secs(2)));
+
+        game.apply(Action::HardDrop);
+        while !game.is_over() {
+            game.tick();
+        }
+
+        assert_eq!(game.outcome(), Some(Outcome::TimeUp));
+        assert_eq!(game.time_left(), Some(Duration::ZERO));
+        assert_eq!(game.record(), Some(Record::Score(game.scoring().score())));
+    }
+
+    #[test]
+    fn records_compare() {
+        let fast = Record::Time(Duration::from_secs(30));
+        let slow = Record::Time(Duration::from_secs(40));
+
+        assert!(fast.beats(&slow));
+        assert!(!slow.beats(&fast));
+        assert!(Record::Score(10).beats(&Record::Score(5)));
+    }
+
+    #[test]
+    fn time_format() {
+        assert_eq!(format_time(Duration::from_millis(65_250)), "1:05.250");
+        assert_eq!(format_time(Duration::ZERO), "0:00.000");
+    }
+}
@@ -0,0 +1,262 @@
+type Blocks = [(i8, i8); 4];
+type Kicks = [(i8, i8); 5];
+
+/// Cell offsets of every tetromino in each rotation state, relative to the
+/// rotation center. Follows the Super Rotation System, with y pointing up.
+const SHAPES: [[Blocks; 4]; 7] = [
+    // I
+    [
+        [(-1, 0), (0, 0), (1, 0), (2, 0)],
+        [(1, 1), (1, 0), (1, -1), (1, -2)],
+        [(2, -1), (1, -1), (0, -1), (-1, -1)],
+        [(0, -2), (0, -1), (0, 0), (0, 1)],
+    ],
+    // O
+    [
+        [(0, 0), (1, 0), (0, 1), (1, 1)],
+        [(0, 0), (1, 0), (0, 1), (1, 1)],
+        [(0, 0), (1, 0), (0, 1), (1, 1)],
+        [(0, 0), (1, 0), (0, 1), (1, 1)],
+    ],
+    // T
+    [
+        [(-1, 0), (0, 0), (1, 0), (0, 1)],
+        [(0, 1), (0, 0), (0, -1), (1, 0)],
+        [(1, 0), (0, 0), (-1, 0), (0, -1)],
+        [(0, -1), (0, 0), (0, 1), (-1, 0)],
+    ],
+    // S
+    [
+        [(0, 1), (1, 1), (-1, 0), (0, 0)],
+        [(1, 0), (1, -1), (0, 1), (0, 0)],
+        [(0, -1), (-1, -1), (1, 0), (0, 0)],
+        [(-1, 0), (-1, 1), (0, -1), (0, 0)],
+    ],
+    // Z
+    [
+        [(-1, 1), (0, 1), (0, 0), (1, 0)],
+        [(1, 1), (1, 0), (0, 0), (0, -1)],
+        [(1, -1), (0, -1), (0, 0), (-1, 0)],
+        [(-1, -1), (-1, 0), (0, 0), (0, 1)],
+    ],
+    // J
+    [
+        [(-1, 1), (-1, 0), (0, 0), (1, 0)],
+        [(1, 1), (0, 1), (0, 0), (0, -1)],
+        [(1, -1), (1, 0), (0, 0), (-1, 0)],
+        [(-1, -1), (0, -1), (0, 0), (0, 1)],
+    ],
+    // L
+    [
+        [(1, 1), (-1, 0), (0, 0), (1, 0)],
+        [(1, -1), (0, 1), (0, 0), (0, -1)],
+        [(-1, -1), (1, 0), (0, 0), (-1, 0)],
+        [(-1, 1), (0, -1), (0, 0), (0, 1)],
+    ],
+];
+
+/// Super Rotation System wall kicks, tried in order until one fits. Indexed by
+/// the rotation state before the turn and by the direction of the turn.
+const JLSTZ_KICKS: [[Kicks; 2]; 4] = [
+    [
+        [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+        [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    ],
+    [
+        [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+        [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    ],
+    [
+        [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+        [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    ],
+    [
+        [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+        [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    ],
+];
+
+const I_KICKS: [[Kicks; 2]; 4] = [
+    [
+        [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+        [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    ],
+    [
+        [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+        [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    ],
+    [
+        [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+        [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    ],
+    [
+        [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+        [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    ],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tetromino {
+    I,
+    O,
+    T,
+    S,
+    Z,
+    J,
+    L,
+}
+
+impl Tetromino {
+    pub const ALL: [Tetromino; 7] = [
+        Tetromino::I,
+        Tetromino::O,
+        Tetromino::T,
+        Tetromino::S,
+        Tetromino::Z,
+        Tetromino::J,
+        Tetromino::L,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Turn {
+    Right,
+    Left,
+}
+
+/// The rotation states called 0, R, 2 and L in the guideline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    #[default]
+    Spawn,
+    Right,
+    Flip,
+    Left,
+}
+
+impl Rotation {
+    pub fn turned(self, turn: Turn) -> Self {
+        use Rotation::*;
+
+        match (self, turn) {
+            (Spawn, Turn::Right) | (Flip, Turn::Left) => Right,
+            (Right, Turn::Right) | (Left, Turn::Left) => Flip,
+            (Flip, Turn::Right) | (Spawn, Turn::Left) => Left,
+            (Left, Turn::Right) | (Right, Turn::Left) => Spawn,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Piece {
+    pub x: i8,
+    pub y: i8,
+    pub tetromino: Tetromino,
+    pub rotation: Rotation,
+}
+
+impl Piece {
+    pub fn new(tetromino: Tetromino, x: i8, y: i8) -> Self {
+        Self {
+            x,
+            y,
+            tetromino,
+            rotation: Rotation::Spawn,
+        }
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = (i8, i8)> + '_ {
+        SHAPES[self.tetromino as usize][self.rotation as usize]
+            .iter()
+            .map(move |(dx, dy)| (self.x + dx, self.y + dy))
+    }
+
+    pub fn moved(mut self, dx: i8, dy: i8) -> Self {
+        self.x += dx;
+        self.y += dy;
+        self
+    }
+
+    pub fn rotated(mut self, turn: Turn) -> Self {
+        self.rotation = self.rotation.turned(turn);
+        self
+    }
+
+    /// Translations to try, in order, when turning the piece.
+    pub fn kicks(&self, turn: Turn) -> &'static [(i8, i8)] {
+        let table = match self.tetromino {
+            Tetromino::I => &I_KICKS,
+            Tetromino::O => return &[(0, 0)],
+            _ => &JLSTZ_KICKS,
+        };
+
+        &table[self.rotation as usize][turn as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Piece, Rotation, Tetromino, Turn, I_KICKS, JLSTZ_KICKS, SHAPES};
+
+    const ROTATIONS: [Rotation; 4] = [
+        Rotation::Spawn,
+        Rotation::Right,
+        Rotation::Flip,
+        Rotation::Left,
+    ];
+
+    #[test]
+    fn rotate_and_back() {
+        for tetromino in Tetromino::ALL {
+            let piece = Piece::new(tetromino, 0, 10);
+
+            assert_eq!(piece.rotated(Turn::Right).rotated(Turn::Left), piece);
+            assert_eq!(
+                piece
+                    .rotated(Turn::Right)
+                    .rotated(Turn::Right)
+                    .rotated(Turn::Right)
+                    .rotated(Turn::Right),
+                piece
+            );
+        }
+    }
+
+    #[test]
+    fn states_are_quarter_turns() {
+        // rotating the cells of one state around the rotation center must give
+        // the cells of the next state; the I and O centers sit between cells
+        let centers = [(1, -1), (1, 1), (0, 0), (0, 0), (0, 0), (0, 0), (0, 0)];
+
+        for (states, (cx, cy)) in SHAPES.iter().zip(centers) {
+            for rotation in ROTATIONS {
+                let next = rotation.turned(Turn::Right);
+                let mut rotated: Vec<_> = states[rotation as usize]
+                    .iter()
+                    .map(|(x, y)| ((2 * y - cy + cx) / 2, (cx - 2 * x + cy) / 2))
+                    .collect();
+                let mut expected = states[next as usize].to_vec();
+
+                rotated.sort();
+                expected.sort();
+                assert_eq!(rotated, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn kicks_are_reversible() {
+        // turning back must try the same translations in the opposite direction
+        for table in [JLSTZ_KICKS, I_KICKS] {
+            for rotation in ROTATIONS {
+                let next = rotation.turned(Turn::Right);
+                let forward = table[rotation as usize][Turn::Right as usize];
+                let back = table[next as usize][Turn::Left as usize];
+
+                for ((x, y), (back_x, back_y)) in forward.into_iter().zip(back) {
+                    assert_eq!((x, y), (-back_x, -back_y));
+                }
+            }
+        }
+    }
+}
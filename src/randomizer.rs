@@ -0,0 +1,181 @@
+use std::fmt::Debug;
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::piece::Tetromino;
+
+/// Source of the sequence of pieces handed out during a game.
+pub trait Randomizer: Debug + Send + Sync {
+    fn next(&mut self) -> Tetromino;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RandomizerKind {
+    /// Shuffled bags holding every tetromino once.
+    #[default]
+    Bag7,
+    /// Shuffled bags holding every tetromino twice.
+    Bag14,
+    /// Every tetromino is equally likely every time.
+    Random,
+    /// TGM style: rerolls pieces found in the last four dealt.
+    History,
+}
+
+impl RandomizerKind {
+    pub fn build(self, seed: u64) -> Box<dyn Randomizer> {
+        match self {
+            RandomizerKind::Bag7 => Box::new(Bag::new(seed, 1)),
+            RandomizerKind::Bag14 => Box::new(Bag::new(seed, 2)),
+            RandomizerKind::Random => Box::new(Random::new(seed)),
+            RandomizerKind::History => Box::new(History::new(seed, 6)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Bag {
+    rng: StdRng,
+    copies: usize,
+    bag: Vec<Tetromino>,
+}
+
+impl Bag {
+    pub fn new(seed: u64, copies: usize) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            copies,
+            bag: Vec::with_capacity(copies * Tetromino::ALL.len()),
+        }
+    }
+}
+
+impl Randomizer for Bag {
+    fn next(&mut self) -> Tetromino {
+        if self.bag.is_empty() {
+            for _ in 0..self.copies {
+                self.bag.extend(Tetromino::ALL);
+            }
+            self.bag.shuffle(&mut self.rng);
+        }
+
+        self.bag.pop().unwrap()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Random {
+    rng: StdRng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Randomizer for Random {
+    fn next(&mut self) -> Tetromino {
+        *Tetromino::ALL.choose(&mut self.rng).unwrap()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct History {
+    rng: StdRng,
+    rolls: u8,
+    history: [Tetromino; 4],
+    first: bool,
+}
+
+impl History {
+    pub fn new(seed: u64, rolls: u8) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(seed),
+            rolls,
+            history: [Tetromino::Z, Tetromino::S, Tetromino::S, Tetromino::Z],
+            first: true,
+        }
+    }
+}
+
+impl Randomizer for History {
+    fn next(&mut self) -> Tetromino {
+        let piece = if self.first {
+            // never start with a piece that forces an overhang
+            self.first = false;
+            [Tetromino::I, Tetromino::T, Tetromino::J, Tetromino::L][self.rng.gen_range(0..4)]
+        } else {
+            let mut piece = *Tetromino::ALL.choose(&mut self.rng).unwrap();
+            for _ in 1..self.rolls {
+                if !self.history.contains(&piece) {
+                    break;
+                }
+                piece = *Tetromino::ALL.choose(&mut self.rng).unwrap();
+            }
+            piece
+        };
+
+        self.history.rotate_left(1);
+        self.history[3] = piece;
+        piece
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Randomizer, RandomizerKind};
+    use crate::Tetromino;
+
+    fn deal(randomizer: &mut dyn Randomizer, count: usize) -> Vec<Tetromino> {
+        (0..count).map(|_| randomizer.next()).collect()
+    }
+
+    #[test]
+    fn bag_deals_every_piece_once() {
+        let mut randomizer = RandomizerKind::Bag7.build(42);
+
+        for _ in 0..10 {
+            let mut bag = deal(&mut *randomizer, 7);
+            bag.sort_by_key(|&tetromino| tetromino as usize);
+            assert_eq!(bag, Tetromino::ALL);
+        }
+    }
+
+    #[test]
+    fn double_bag_deals_every_piece_twice() {
+        let mut randomizer = RandomizerKind::Bag14.build(42);
+
+        for _ in 0..10 {
+            let bag = deal(&mut *randomizer, 14);
+            for tetromino in Tetromino::ALL {
+                assert_eq!(bag.iter().filter(|&&t| t == tetromino).count(), 2);
+            }
+        }
+    }
+
+    #[test]
+    fn history_never_starts_with_overhang() {
+        for seed in 0..100 {
+            let first = RandomizerKind::History.build(seed).next();
+            assert!(![Tetromino::S, Tetromino::Z, Tetromino::O].contains(&first));
+        }
+    }
+
+    #[test]
+    fn same_seed_same_sequence() {
+        for kind in [
+            RandomizerKind::Bag7,
+            RandomizerKind::Bag14,
+            RandomizerKind::Random,
+            RandomizerKind::History,
+        ] {
+            assert_eq!(
+                deal(&mut *kind.build(7), 100),
+                deal(&mut *kind.build(7), 100)
+            );
+        }
+    }
+}
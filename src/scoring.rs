@@ -0,0 +1,231 @@
+/// Points for clearing zero to four lines at once, before the level
+/// multiplier.
+const LINE_CLEAR_POINTS: [u64; 5] = [0, 100, 300, 500, 800];
+const MINI_T_SPIN_POINTS: [u64; 3] = [100, 200, 400];
+const T_SPIN_POINTS: [u64; 4] = [400, 800, 1200, 1600];
+/// Bonus for leaving the board empty, by the number of lines cleared.
+const PERFECT_CLEAR_POINTS: [u64; 4] = [800, 1200, 1800, 2000];
+const BACK_TO_BACK_PERFECT_CLEAR_POINTS: u64 = 3200;
+const COMBO_POINTS: u64 = 50;
+pub(crate) const LINES_PER_LEVEL: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Spin {
+    #[default]
+    None,
+    /// A T-spin with only one of the corners in front of the T occupied.
+    Mini,
+    TSpin,
+}
+
+/// The outcome of locking a piece that cleared lines or spun into place.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LineClear {
+    pub lines: u8,
+    pub spin: Spin,
+    /// Number of consecutive clears before this one.
+    pub combo: u32,
+    /// The clear follows another difficult one and earns the bonus.
+    pub back_to_back: bool,
+    /// The clear left the board empty.
+    pub perfect_clear: bool,
+}
+
+impl LineClear {
+    /// Tetrises and spins that clear lines keep the back-to-back chain going.
+    pub fn is_difficult(&self) -> bool {
+        self.lines >= 4 || (self.lines > 0 && self.spin != Spin::None)
+    }
+}
+
+/// Guideline scoring: line clears and combos are multiplied by the level,
+/// consecutive difficult clears earn a back-to-back bonus of one half.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scoring {
+    score: u64,
+    start_level: u32,
+    level: u32,
+    lines: u32,
+    combo: Option<u32>,
+    back_to_back: bool,
+}
+
+impl Scoring {
+    pub fn new(start_level: u32) -> Self {
+        let start_level = start_level.max(1);
+
+        Self {
+            score: 0,
+            start_level,
+            level: start_level,
+            lines: 0,
+            combo: None,
+            back_to_back: false,
+        }
+    }
+
+    pub fn score(&self) -> u64 {
+        self.score
+    }
+
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    pub fn lines(&self) -> u32 {
+        self.lines
+    }
+
+    /// Number of consecutive clears after the first one, if the last piece
+    /// cleared a line.
+    pub fn combo(&self) -> Option<u32> {
+        self.combo
+    }
+
+    /// Whether the next difficult clear earns the back-to-back bonus.
+    pub fn back_to_back(&self) -> bool {
+        self.back_to_back
+    }
+
+    pub fn soft_drop(&mut self, rows: u8) {
+        self.score += rows as u64;
+    }
+
+    pub fn hard_drop(&mut self, rows: u8) {
+        self.score += 2 * rows as u64;
+    }
+
+    /// Scores a locked piece and returns the points awarded.
+    pub fn lock(&mut self, clear: &LineClear) -> u64 {
+        let table: &[u64] = match clear.spin {
+            Spin::None => &LINE_CLEAR_POINTS,
+            Spin::Mini => &MINI_T_SPIN_POINTS,
+            Spin::TSpin => &T_SPIN_POINTS,
+        };
+        let mut points = table[(clear.lines as usize).min(table.len() - 1)];
+
+        if clear.lines == 0 {
+            // spins without a clear score, but neither break nor extend chains
+            self.combo = None;
+        } else {
+            let difficult = clear.is_difficult();
+            let back_to_back = difficult && self.back_to_back;
+            if back_to_back {
+                points += points / 2;
+            }
+            self.back_to_back = difficult;
+
+            let combo = self.combo.map_or(0, |combo| combo + 1);
+            self.combo = Some(combo);
+            points += COMBO_POINTS * combo as u64;
+
+            if clear.perfect_clear {
+                points += if clear.lines >= 4 && back_to_back {
+                    BACK_TO_BACK_PERFECT_CLEAR_POINTS
+                } else {
+                    PERFECT_CLEAR_POINTS[(clear.lines.min(4) - 1) as usize]
+                };
+            }
+        }
+
+        let points = points * self.level as u64;
+        self.score += points;
+        self.lines += clear.lines as u32;
+        self.level = self.start_level + self.lines / LINES_PER_LEVEL;
+
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LineClear, Scoring, Spin};
+
+    fn clear(scoring: &mut Scoring, lines: u8) -> u64 {
+        scoring.lock(&LineClear {
+            lines,
+            ..LineClear::default()
+        })
+    }
+
+    #[test]
+    fn clears_scale_with_level() {
+        let mut scoring = Scoring::new(3);
+
+        assert_eq!(clear(&mut scoring, 1), 300);
+        clear(&mut scoring, 0);
+        assert_eq!(clear(&mut scoring, 4), 2400);
+        assert_eq!(scoring.score(), 2700);
+    }
+
+    #[test]
+    fn combo_and_back_to_back() {
+        let mut scoring = Scoring::new(1);
+
+        assert_eq!(clear(&mut scoring, 4), 800);
+        assert_eq!(clear(&mut scoring, 4), 1200 + 50);
+        assert_eq!(clear(&mut scoring, 1), 100 + 100);
+        assert!(!scoring.back_to_back());
+
+        clear(&mut scoring, 0);
+        assert_eq!(scoring.combo(), None);
+        assert_eq!(clear(&mut scoring, 4), 800);
+    }
+
+    #[test]
+    fn t_spins() {
+        let mut scoring = Scoring::new(1);
+        let spin = |lines, spin| LineClear {
+            lines,
+            spin,
+            ..LineClear::default()
+        };
+
+        assert_eq!(scoring.lock(&spin(0, Spin::TSpin)), 400);
+        assert_eq!(scoring.lock(&spin(1, Spin::Mini)), 200);
+        assert_eq!(scoring.lock(&spin(0, Spin::Mini)), 100);
+        // the zero line spin in between keeps the back-to-back chain
+        assert_eq!(scoring.lock(&spin(2, Spin::TSpin)), 1800);
+        assert_eq!(scoring.score(), 2500);
+    }
+
+    #[test]
+    fn perfect_clear_bonus() {
+        let mut scoring = Scoring::new(2);
+        let perfect_clear = |lines| LineClear {
+            lines,
+            perfect_clear: true,
+            ..LineClear::default()
+        };
+
+        assert_eq!(scoring.lock(&perfect_clear(1)), (100 + 800) * 2);
+        clear(&mut scoring, 0);
+        assert_eq!(scoring.lock(&perfect_clear(4)), (800 + 2000) * 2);
+        clear(&mut scoring, 0);
+        assert_eq!(scoring.lock(&perfect_clear(4)), (1200 + 3200) * 2);
+    }
+
+    #[test]
+    fn level_rises_every_ten_lines() {
+        let mut scoring = Scoring::new(1);
+
+        for _ in 0..2 {
+            clear(&mut scoring, 4);
+            clear(&mut scoring, 0);
+        }
+        assert_eq!(scoring.level(), 1);
+
+        clear(&mut scoring, 2);
+        assert_eq!(scoring.lines(), 10);
+        assert_eq!(scoring.level(), 2);
+    }
+
+    #[test]
+    fn drops_score_per_row() {
+        let mut scoring = Scoring::new(5);
+        scoring.soft_drop(3);
+        scoring.hard_drop(10);
+
+        assert_eq!(scoring.score(), 23);
+    }
+}
@@ -1,288 +1,242 @@
 use bevy::prelude::*;
-use ignore_result::Ignore;
-use rand::{thread_rng, Rng};
+use tetris::{Config, GameEvent, Phase, Piece, Tetromino};
 
 use crate::{
-    bricks::{
-        brick_bundle, spawn_brick, to_brick_coordinates, to_brick_translation, Brick, Bricks,
-    },
+    bricks::{brick_bundle, to_brick_translation, LinesCleared, TetrominoColors},
     controls::ControlEvent,
-    tick::Tick,
-    GameState, BRICK_COLS_RANGE, BRICK_ROWS, BRICK_SIZE,
+    Engine, GameState, Settings, BRICK_SIZE,
 };
 
-#[derive(Component, Default, Clone, Debug)]
-struct Shape;
+const SHAPE_Z: f32 = 1.;
+const GHOST_Z: f32 = 0.5;
+const GHOST_ALPHA: f32 = 0.3;
+
+#[derive(Component, Clone, Debug)]
+pub struct Shape(pub Tetromino);
+
+#[derive(Component, Clone, Debug)]
+struct Ghost;
 
 #[derive(Component, Clone, Debug)]
 struct ShapeBrick;
 
+type ShapeBrickQuery<'w, 's> =
+    Query<'w, 's, (&'static mut Transform, &'static mut Sprite), With<ShapeBrick>>;
+type GhostQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        &'static mut Visibility,
+        &'static Children,
+    ),
+    (With<Ghost>, Without<ShapeBrick>),
+>;
+
+#[derive(Debug, Clone, Default)]
+pub struct ShapeMoved;
+
+#[derive(Debug, Clone, Default)]
+pub struct ShapeLocked;
+
 #[derive(Debug, Clone, Default)]
 pub struct ShapeSpawned;
 
+#[derive(Debug, Clone, Deref)]
+pub struct ShapeHardDropped(pub u8);
+
 pub struct ShapePlugin;
 
 impl Plugin for ShapePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<ShapeSpawned>()
+        app.add_event::<ShapeMoved>()
+            .add_event::<ShapeLocked>()
+            .add_event::<ShapeSpawned>()
+            .add_event::<ShapeHardDropped>()
             .add_system(reset.in_schedule(OnEnter(GameState::Starting)))
-            .add_system(rotate.in_set(OnUpdate(GameState::InGame)))
-            .add_systems(
-                (check_game_over, move_down)
-                    .chain()
-                    .in_set(OnUpdate(GameState::InGame)),
-            );
+            .add_system(forward_events.in_set(OnUpdate(GameState::InGame)))
+            .add_system(update_shape.run_if(resource_exists::<Engine>()))
+            .add_system(update_ghost.run_if(resource_exists::<Engine>()))
+            .add_system(toggle_ghost);
     }
 }
 
-fn check_game_over(
+fn forward_events(
+    mut engine: ResMut<Engine>,
     mut next_state: ResMut<NextState<GameState>>,
-    query: Query<(&Transform, &Children), (With<Shape>, Changed<Transform>)>,
-    child_query: Query<(&Transform, &Sprite), (With<ShapeBrick>, Without<Shape>)>,
-    bricks: Res<Bricks>,
+    mut move_events: EventWriter<ShapeMoved>,
+    mut lock_events: EventWriter<ShapeLocked>,
+    mut spawn_events: EventWriter<ShapeSpawned>,
+    mut drop_events: EventWriter<ShapeHardDropped>,
+    mut clear_events: EventWriter<LinesCleared>,
 ) {
-    let bricks = &*bricks;
-
-    for (transform, children) in &query {
-        let children: Vec<_> = children
-            .into_iter()
-            .map(|child| child_query.get(*child).unwrap())
-            .collect();
-
-        if children
-            .iter()
-            .any(|(child_transform, _)| collides(transform, child_transform, bricks))
-        {
-            println!("game over");
-            next_state.set(GameState::GameOver);
+    // draining events does not change the game, so it should not trigger a redraw
+    for event in engine.bypass_change_detection().drain_events() {
+        match event {
+            GameEvent::Moved => move_events.send_default(),
+            GameEvent::Locked => lock_events.send_default(),
+            GameEvent::Spawned => spawn_events.send_default(),
+            GameEvent::Held => (),
+            GameEvent::HardDropped(rows) => drop_events.send(ShapeHardDropped(rows)),
+            GameEvent::Cleared(clear) => clear_events.send(LinesCleared(clear)),
+            GameEvent::GameOver => {
+                println!("game over");
+                next_state.set(GameState::GameOver);
+            }
         }
     }
 }
 
-fn rotate(
-    mut query: Query<(&mut Transform, &Children), With<Shape>>,
-    child_query: Query<(&Transform, &Sprite), (With<ShapeBrick>, Without<Shape>)>,
-    mut control_events: EventReader<ControlEvent>,
-    mut bricks: ResMut<Bricks>,
+fn update_shape(
+    mut commands: Commands,
+    engine: Res<Engine>,
+    colors: Res<TetrominoColors>,
+    mut query: Query<(&mut Shape, &mut Transform, &mut Visibility, &Children), Without<ShapeBrick>>,
+    mut child_query: ShapeBrickQuery,
 ) {
-    if let Ok((mut transform, children)) = query.get_single_mut() {
-        let children: Vec<_> = children
-            .into_iter()
-            .map(|child| child_query.get(*child).unwrap())
-            .collect();
+    if !engine.is_changed() && !colors.is_changed() {
+        return;
+    }
 
-        for next_transform in control_events
-            .iter()
-            .filter_map(transform_from_control_event)
-        {
-            try_move_shape(next_transform, &mut transform, &children, &mut bricks).ignore();
-        }
+    let piece = engine.piece();
+    let color = colors.get(piece.tetromino);
+
+    if let Ok((mut shape, mut transform, mut visibility, children)) = query.get_single_mut() {
+        shape.0 = piece.tetromino;
+        // there is no falling piece during delays
+        *visibility = if in_play(engine.phase()) {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+        move_shape(
+            engine.config(),
+            piece,
+            SHAPE_Z,
+            color,
+            &mut transform,
+            children,
+            &mut child_query,
+        );
+    } else {
+        let entity = spawn_shape(&mut commands, engine.config(), piece, SHAPE_Z, color);
+        commands.entity(entity).insert(Shape(piece.tetromino));
     }
 }
 
-fn move_down(
+fn update_ghost(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut Transform, &Children), With<Shape>>,
-    child_query: Query<(&Transform, &Sprite), (With<ShapeBrick>, Without<Shape>)>,
-    mut tick_events: EventReader<Tick>,
-    mut spawn_events: EventWriter<ShapeSpawned>,
-    mut bricks: ResMut<Bricks>,
+    engine: Res<Engine>,
+    colors: Res<TetrominoColors>,
+    settings: Res<Settings>,
+    mut query: GhostQuery,
+    mut child_query: ShapeBrickQuery,
 ) {
-    let commands = &mut commands;
-
-    if let Ok((entity, mut transform, children)) = query.get_single_mut() {
-        let children: Vec<_> = children
-            .into_iter()
-            .map(|child| child_query.get(*child).unwrap())
-            .collect();
+    if !engine.is_changed() && !colors.is_changed() && !settings.is_changed() {
+        return;
+    }
 
-        for move_down in tick_events.iter().map(|_| Transform {
-            translation: Vec3::Y * -BRICK_SIZE,
-            ..default()
-        }) {
-            let result = try_move_shape(move_down, &mut transform, &children, &mut bricks);
-            if result.is_err() {
-                // if shape could not move down
-                shape_to_bricks(commands, &mut bricks, &*transform, &children);
-                commands.entity(entity).despawn_recursive();
-                spawn_shape(commands);
-                spawn_events.send_default();
-                return;
-            }
-        }
+    let ghost = engine.ghost();
+    let color = colors.get(ghost.tetromino).with_a(GHOST_ALPHA);
+
+    if let Ok((mut transform, mut visibility, children)) = query.get_single_mut() {
+        *visibility = if settings.ghost && in_play(engine.phase()) {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+        move_shape(
+            engine.config(),
+            &ghost,
+            GHOST_Z,
+            color,
+            &mut transform,
+            children,
+            &mut child_query,
+        );
+    } else {
+        let entity = spawn_shape(&mut commands, engine.config(), &ghost, GHOST_Z, color);
+        commands.entity(entity).insert(Ghost);
     }
 }
 
-fn try_move_shape(
-    next_transform: Transform,
-    transform: &mut Mut<Transform>,
-    children: &Vec<(&Transform, &Sprite)>,
-    bricks: &mut ResMut<Bricks>,
-) -> Result<(), ()> {
-    let next_transform = Transform {
-        translation: next_transform.translation + transform.translation,
-        rotation: next_transform.rotation * transform.rotation,
-        ..default()
-    };
-    if !children
+fn toggle_ghost(mut control_events: EventReader<ControlEvent>, mut settings: ResMut<Settings>) {
+    for _ in control_events
         .iter()
-        .any(|(child_transform, _)| collides(&next_transform, child_transform, bricks))
+        .filter(|&&event| event == ControlEvent::ToggleGhost)
     {
-        **transform = next_transform;
-        Ok(())
-    } else {
-        Err(())
+        settings.ghost = !settings.ghost;
     }
 }
 
-fn transform_from_control_event(event: &ControlEvent) -> Option<Transform> {
-    match event {
-        ControlEvent::SpeedupStart | ControlEvent::SpeedupEnd | ControlEvent::Pause => None,
-        ControlEvent::Left => Some(Transform {
-            translation: Vec3::X * -BRICK_SIZE,
-            ..default()
-        }),
-        ControlEvent::Right => Some(Transform {
-            translation: Vec3::X * BRICK_SIZE,
-            ..default()
-        }),
-        ControlEvent::RotateRight => Some(Transform {
-            rotation: Quat::from_rotation_z(-std::f32::consts::PI / 2.0),
-            ..default()
-        }),
-        ControlEvent::RotateLeft => Some(Transform {
-            rotation: Quat::from_rotation_z(std::f32::consts::PI / 2.0),
-            ..default()
-        }),
-    }
+/// There is no piece to show while lines clear or the next piece enters.
+fn in_play(phase: Phase) -> bool {
+    !matches!(phase, Phase::LineClear | Phase::Entry)
 }
 
-fn shape_to_bricks(
-    commands: &mut Commands,
-    bricks: &mut Bricks,
-    transform: &Transform,
-    children: &[(&Transform, &Sprite)],
+fn move_shape(
+    config: &Config,
+    piece: &Piece,
+    z: f32,
+    color: Color,
+    transform: &mut Transform,
+    children: &Children,
+    child_query: &mut ShapeBrickQuery,
 ) {
-    for (&child, sprite) in children {
-        let child_position = transform.mul_transform(child);
-        let coords = to_brick_coordinates(child_position.translation);
-        spawn_brick(
-            commands,
-            &mut *bricks,
-            Brick {
-                x: coords.0,
-                y: coords.1,
-            },
-            sprite.color,
-        );
+    transform.translation = to_brick_translation(config, piece.x, piece.y)
+        .truncate()
+        .extend(z);
+
+    for (child, translation) in children.iter().zip(brick_translations(piece)) {
+        let (mut child_transform, mut sprite) = child_query.get_mut(*child).unwrap();
+        child_transform.translation = translation;
+        sprite.color = color;
     }
 }
 
-fn collides(parent_transform: &Transform, child_transform: &Transform, bricks: &Bricks) -> bool {
-    let position = parent_transform.mul_transform(*child_transform);
-    let (x, y) = to_brick_coordinates(position.translation.clone());
-
-    if !BRICK_COLS_RANGE.contains(&x) {
-        return true;
-    }
-
-    if y < 0 {
-        return true;
-    }
-
-    if bricks.contains_key(&(x, y)) {
-        return true;
-    }
-
-    false
-}
-
-fn spawn_shape(commands: &mut Commands) {
+fn spawn_shape(
+    commands: &mut Commands,
+    config: &Config,
+    piece: &Piece,
+    z: f32,
+    color: Color,
+) -> Entity {
     commands
         .spawn(SpatialBundle {
             transform: Transform {
-                translation: to_brick_translation(0, BRICK_ROWS),
+                translation: to_brick_translation(config, piece.x, piece.y)
+                    .truncate()
+                    .extend(z),
                 ..default()
             },
             ..default()
         })
-        .insert(Shape {})
         .with_children(|parent| {
-            let color = Color::hsl(thread_rng().gen_range(0.0..360.0), 1.0, 0.6);
-            let make_brick = |x: i8, y: i8| {
+            for translation in brick_translations(piece) {
                 parent
-                    .spawn(brick_bundle(
-                        Vec3::new(x as f32 * BRICK_SIZE, y as f32 * BRICK_SIZE, 1.),
-                        color,
-                    ))
+                    .spawn(brick_bundle(translation, color))
                     .insert(ShapeBrick);
-            };
-
-            make_random_shape(make_brick);
-        });
+            }
+        })
+        .id()
 }
 
-fn make_random_shape(mut make_brick: impl FnMut(i8, i8)) {
-    let choice = thread_rng().gen_range(0..=6);
-
-    match choice {
-        0 => {
-            // T
-            make_brick(0, 0);
-            make_brick(1, 0);
-            make_brick(-1, 0);
-            make_brick(0, -1);
-        }
-        1 => {
-            // I
-            make_brick(-1, 0);
-            make_brick(0, 0);
-            make_brick(1, 0);
-            make_brick(2, 0);
-        }
-        2 => {
-            // L
-            make_brick(-2, 0);
-            make_brick(-1, 0);
-            make_brick(0, 0);
-            make_brick(0, -1);
-        }
-        3 => {
-            // L'
-            make_brick(2, 0);
-            make_brick(1, 0);
-            make_brick(0, 0);
-            make_brick(0, -1);
-        }
-        4 => {
-            // S
-            make_brick(0, 0);
-            make_brick(1, 0);
-            make_brick(-1, -1);
-            make_brick(0, -1);
-        }
-        5 => {
-            // Z
-            make_brick(0, 0);
-            make_brick(-1, 0);
-            make_brick(1, -1);
-            make_brick(0, -1);
-        }
-        6 => {
-            // O
-            make_brick(0, 0);
-            make_brick(1, 0);
-            make_brick(0, -1);
-            make_brick(1, -1);
-        }
-        _ => unreachable!(),
-    }
+fn brick_translations(piece: &Piece) -> impl Iterator<Item = Vec3> + '_ {
+    piece.cells().map(|(x, y)| {
+        Vec3::new(
+            (x - piece.x) as f32 * BRICK_SIZE,
+            (y - piece.y) as f32 * BRICK_SIZE,
+            1.,
+        )
+    })
 }
 
-fn reset(mut commands: Commands, query: Query<Entity, With<Shape>>) {
-    for entity in &query {
+fn reset(
+    mut commands: Commands,
+    shapes: Query<Entity, With<Shape>>,
+    ghosts: Query<Entity, With<Ghost>>,
+) {
+    for entity in shapes.iter().chain(&ghosts) {
         commands.entity(entity).despawn_recursive();
     }
-
-    spawn_shape(&mut commands);
 }
@@ -1,67 +1,89 @@
-use std::time::Duration;
-
 use bevy::prelude::*;
+use tetris::{Action, TICKS_PER_SECOND};
 
-use crate::{controls::ControlEvent, GameState, GameStats};
+use crate::{controls::ControlEvent, Engine, GameState};
 
-#[derive(Resource, Debug)]
-pub struct TickTimer {
-    timer: Timer,
-    in_speedup: bool,
-}
-
-impl Default for TickTimer {
-    fn default() -> Self {
-        Self {
-            timer: Timer::from_seconds(1.0, TimerMode::Repeating),
-            in_speedup: false,
-        }
-    }
-}
+#[derive(Resource, Default, Debug, Deref, DerefMut)]
+struct PendingActions(Vec<Action>);
 
 pub struct TickPlugin;
 
 impl Plugin for TickPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<Tick>()
+        app.insert_resource(FixedTime::new_from_secs(1. / TICKS_PER_SECOND as f32))
             .add_system(reset.in_schedule(OnEnter(GameState::Starting)))
-            .add_systems(
-                (speedup, tick_system)
-                    .chain()
-                    .in_set(OnUpdate(GameState::InGame)),
+            .add_system(queue_actions.in_set(OnUpdate(GameState::InGame)))
+            // `OnUpdate` sets only carry the state condition in the main
+            // schedule, so the fixed step needs its own
+            .add_system(
+                tick_system
+                    .run_if(in_state(GameState::InGame))
+                    .in_schedule(CoreSchedule::FixedUpdate),
             );
     }
 }
 
-#[derive(Default)]
-pub struct Tick;
-fn tick_system(
-    time: Res<Time>,
-    mut writer: EventWriter<Tick>,
-    mut timer: ResMut<TickTimer>,
-    stats: Res<GameStats>,
+// inputs are applied on the next fixed step, so that the game only depends on
+// the sequence of steps and not on the frame rate
+fn queue_actions(
+    mut pending: ResMut<PendingActions>,
+    mut control_events: EventReader<ControlEvent>,
 ) {
-    let speed = 1.0 + stats.shapes_spawned as f32 * 0.02;
-    let time_step = if timer.in_speedup { 0.03 } else { 1.0 / speed };
+    pending.extend(control_events.iter().filter_map(action_from_control_event));
+}
+
+fn tick_system(mut engine: ResMut<Engine>, mut pending: ResMut<PendingActions>) {
+    for action in pending.drain(..) {
+        engine.apply(action);
+    }
 
-    timer.timer.set_duration(Duration::from_secs_f32(time_step));
-    timer.timer.tick(time.delta());
+    engine.tick();
+}
 
-    if timer.timer.just_finished() {
-        writer.send_default();
+fn action_from_control_event(event: &ControlEvent) -> Option<Action> {
+    match event {
+        ControlEvent::Pause | ControlEvent::ToggleGhost => None,
+        ControlEvent::SpeedupStart => Some(Action::SoftDropStart),
+        ControlEvent::SpeedupEnd => Some(Action::SoftDropEnd),
+        ControlEvent::Left => Some(Action::Left),
+        ControlEvent::Right => Some(Action::Right),
+        ControlEvent::LeftToWall => Some(Action::LeftToWall),
+        ControlEvent::RightToWall => Some(Action::RightToWall),
+        ControlEvent::RotateRight => Some(Action::RotateRight),
+        ControlEvent::RotateLeft => Some(Action::RotateLeft),
+        ControlEvent::HardDrop => Some(Action::HardDrop),
+        ControlEvent::Hold => Some(Action::Hold),
     }
 }
 
 fn reset(mut commands: Commands) {
-    commands.insert_resource(TickTimer::default())
+    commands.insert_resource(PendingActions::default())
 }
 
-fn speedup(mut timer: ResMut<TickTimer>, mut control_events: EventReader<ControlEvent>) {
-    for event in control_events.iter() {
-        match event {
-            ControlEvent::SpeedupStart => timer.in_speedup = true,
-            ControlEvent::SpeedupEnd => timer.in_speedup = false,
-            _ => (),
-        }
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use tetris::{Config, Game};
+
+    use super::{PendingActions, TickPlugin};
+    use crate::{Engine, GameState};
+
+    #[test]
+    fn fixed_step_only_runs_in_game() {
+        let mut app = App::new();
+        app.add_state::<GameState>().add_plugin(TickPlugin);
+
+        // the engine does not exist yet while assets load
+        app.world.run_schedule(CoreSchedule::FixedUpdate);
+
+        app.insert_resource(Engine(Game::new(Config::default(), 0)))
+            .init_resource::<PendingActions>()
+            .insert_resource(State(GameState::Paused));
+        app.world.run_schedule(CoreSchedule::FixedUpdate);
+        assert_eq!(app.world.resource::<Engine>().ticks(), 0);
+
+        app.insert_resource(State(GameState::InGame));
+        app.world.run_schedule(CoreSchedule::FixedUpdate);
+        assert_eq!(app.world.resource::<Engine>().ticks(), 1);
     }
 }
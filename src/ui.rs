@@ -1,7 +1,13 @@
-use bevy::prelude::*;
+use bevy::{prelude::*, window::PrimaryWindow};
 use bevy_asset_loader::prelude::{AssetCollection, AssetCollectionApp};
 
-use crate::{GameState, GameStats, BRICK_COLS, BRICK_ROWS, BRICK_SIZE};
+use tetris::{format_time, Config, Outcome, Piece, Tetromino, MAX_PREVIEW};
+
+use crate::{
+    bests::PersonalBests,
+    bricks::{LinesCleared, TetrominoColors},
+    Engine, GameState, GameStats, Score, Settings, BRICK_SIZE,
+};
 
 const UI_BG_COLOR: Color = Color::DARK_GRAY;
 
@@ -11,6 +17,29 @@ struct StatusText;
 #[derive(Component, Clone, Debug)]
 pub struct StatisticsText;
 
+/// Briefly announces special clears above the board.
+#[derive(Component, Clone, Debug)]
+struct Banner(Timer);
+
+/// The column holding the board, sized after the board dimensions.
+#[derive(Component, Clone, Debug)]
+struct BoardColumn;
+
+#[derive(Component, Clone, Debug)]
+struct BoardFrame;
+
+#[derive(Component, Clone, Debug)]
+struct HeldPiece;
+
+#[derive(Component, Clone, Debug)]
+struct NextPiece(usize);
+
+#[derive(Component, Clone, Debug)]
+struct PieceBrick;
+
+type PieceBrickQuery<'w, 's> =
+    Query<'w, 's, (&'static mut Style, &'static mut BackgroundColor), With<PieceBrick>>;
+
 #[derive(Resource, AssetCollection)]
 pub struct FontAssets {
     #[asset(path = "fonts/Baloo2-ExtraBold.ttf")]
@@ -26,15 +55,40 @@ impl Plugin for UiPlugin {
             .add_system(hide_status.in_schedule(OnEnter(GameState::InGame)))
             .add_system(show_paused.in_schedule(OnEnter(GameState::Paused)))
             .add_system(show_game_over.in_schedule(OnEnter(GameState::GameOver)))
-            .add_system(update_statistics.in_set(OnUpdate(GameState::InGame)));
+            .add_system(hide_banner.in_schedule(OnEnter(GameState::Starting)))
+            .add_system(update_layout.in_schedule(OnEnter(GameState::Starting)))
+            .add_system(update_statistics.in_set(OnUpdate(GameState::InGame)))
+            .add_system(update_banner.in_set(OnUpdate(GameState::InGame)))
+            .add_system(update_countdown.in_set(OnUpdate(GameState::InGame)))
+            .add_system(update_held_piece.run_if(resource_exists::<Engine>()))
+            .add_system(update_next_pieces.run_if(resource_exists::<Engine>()));
     }
 }
 
-const CENTER_COLUMN_HEIGHT: f32 = BRICK_SIZE * (BRICK_ROWS + 1) as f32;
-const CENTER_COLUMN_WIDTH: f32 = BRICK_SIZE * BRICK_COLS as f32 + 10.;
-const SIDE_COLUMN_WIDTH: f32 = (800. - CENTER_COLUMN_WIDTH) / 2.;
+const SIDE_COLUMN_WIDTH: f32 = 230.;
+const MIN_WINDOW_HEIGHT: f32 = 1000.;
+
+/// Size of the center column and of the board frame inside it.
+fn board_size(config: &Config) -> Vec2 {
+    Vec2::new(
+        BRICK_SIZE * config.width as f32 + 10.,
+        BRICK_SIZE * (config.height + 1) as f32,
+    )
+}
+
+/// Window size fitting the board between the two side columns.
+pub fn window_size(config: &Config) -> Vec2 {
+    let board = board_size(config);
+
+    Vec2::new(
+        board.x + 2. * SIDE_COLUMN_WIDTH,
+        (board.y + 370.).max(MIN_WINDOW_HEIGHT),
+    )
+}
+
+fn setup(mut commands: Commands, assets: Res<FontAssets>, settings: Res<Settings>) {
+    let board = board_size(&settings.config());
 
-fn setup(mut commands: Commands, assets: Res<FontAssets>) {
     commands
         .spawn(NodeBundle {
             style: Style {
@@ -46,25 +100,43 @@ fn setup(mut commands: Commands, assets: Res<FontAssets>) {
             ..default()
         })
         .with_children(|parent| {
-            parent.spawn(NodeBundle {
-                style: Style {
-                    size: Size::new(Val::Px(SIDE_COLUMN_WIDTH), Val::Percent(100.0)),
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Px(SIDE_COLUMN_WIDTH), Val::Percent(100.0)),
+                        padding: UiRect::all(Val::Px(10.0)),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::FlexStart,
+                        ..default()
+                    },
+                    background_color: UI_BG_COLOR.into(),
                     ..default()
-                },
-                background_color: UI_BG_COLOR.into(),
-                ..default()
-            });
+                })
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Hold",
+                        TextStyle {
+                            font: assets.status.cast_weak(),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+
+                    spawn_piece_preview(parent, HeldPiece);
+                });
 
             parent
                 .spawn(NodeBundle {
                     style: Style {
-                        size: Size::new(Val::Px(CENTER_COLUMN_WIDTH), Val::Percent(100.0)),
+                        size: Size::new(Val::Px(board.x), Val::Percent(100.0)),
                         flex_direction: FlexDirection::Column,
                         ..default()
                     },
                     background_color: Color::NONE.into(),
                     ..default()
                 })
+                .insert(BoardColumn)
                 .with_children(|parent| {
                     parent.spawn(NodeBundle {
                         style: Style {
@@ -79,7 +151,7 @@ fn setup(mut commands: Commands, assets: Res<FontAssets>) {
                     parent
                         .spawn(NodeBundle {
                             style: Style {
-                                size: Size::new(Val::Percent(100.), Val::Px(CENTER_COLUMN_HEIGHT)),
+                                size: Size::new(Val::Percent(100.), Val::Px(board.y)),
                                 margin: UiRect::all(Val::Px(5.0)),
                                 flex_direction: FlexDirection::Column,
                                 justify_content: JustifyContent::Center,
@@ -88,7 +160,27 @@ fn setup(mut commands: Commands, assets: Res<FontAssets>) {
                             background_color: Color::NONE.into(),
                             ..default()
                         })
+                        .insert(BoardFrame)
                         .with_children(|parent| {
+                            parent
+                                .spawn(
+                                    TextBundle::from_section(
+                                        "",
+                                        TextStyle {
+                                            font: assets.status.cast_weak(),
+                                            font_size: 40.0,
+                                            color: Color::GOLD,
+                                        },
+                                    )
+                                    .with_text_alignment(TextAlignment::Center)
+                                    .with_style(Style {
+                                        align_self: AlignSelf::Center,
+                                        ..Default::default()
+                                    }),
+                                )
+                                .insert(Visibility::Hidden)
+                                .insert(Banner(Timer::from_seconds(2., TimerMode::Once)));
+
                             parent
                                 .spawn(
                                     TextBundle::from_section(
@@ -134,6 +226,19 @@ fn setup(mut commands: Commands, assets: Res<FontAssets>) {
                     ..default()
                 })
                 .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(
+                        "Next",
+                        TextStyle {
+                            font: assets.status.cast_weak(),
+                            font_size: 30.0,
+                            color: Color::WHITE,
+                        },
+                    ));
+
+                    for index in 0..MAX_PREVIEW {
+                        spawn_piece_preview(parent, NextPiece(index));
+                    }
+
                     parent
                         .spawn(
                             TextBundle::from_section(
@@ -151,30 +256,277 @@ fn setup(mut commands: Commands, assets: Res<FontAssets>) {
         });
 }
 
+fn spawn_piece_preview(parent: &mut ChildBuilder, marker: impl Component) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Px(4. * BRICK_SIZE), Val::Px(2. * BRICK_SIZE)),
+                margin: UiRect::vertical(Val::Px(10.0)),
+                ..default()
+            },
+            background_color: Color::NONE.into(),
+            ..default()
+        })
+        .insert(marker)
+        .with_children(|parent| {
+            for _ in 0..4 {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(BRICK_SIZE * 0.9), Val::Px(BRICK_SIZE * 0.9)),
+                            position_type: PositionType::Absolute,
+                            ..default()
+                        },
+                        background_color: Color::NONE.into(),
+                        ..default()
+                    })
+                    .insert(PieceBrick);
+            }
+        });
+}
+
+fn show_piece(
+    tetromino: Option<Tetromino>,
+    color: Color,
+    children: &Children,
+    brick_query: &mut PieceBrickQuery,
+) {
+    let Some(tetromino) = tetromino else {
+        for child in children {
+            let (_, mut background) = brick_query.get_mut(*child).unwrap();
+            *background = Color::NONE.into();
+        }
+        return;
+    };
+
+    let cells: Vec<_> = Piece::new(tetromino, 0, 0).cells().collect();
+    let min_x = cells.iter().map(|(x, _)| *x).min().unwrap();
+    let max_x = cells.iter().map(|(x, _)| *x).max().unwrap();
+    let min_y = cells.iter().map(|(_, y)| *y).min().unwrap();
+    let max_y = cells.iter().map(|(_, y)| *y).max().unwrap();
+
+    // center the piece in a box of 4x2 bricks
+    let offset_x = (3 - (max_x - min_x)) as f32 / 2.;
+    let offset_y = (1 - (max_y - min_y)) as f32 / 2.;
+
+    for (child, (x, y)) in children.iter().zip(cells) {
+        let (mut style, mut background) = brick_query.get_mut(*child).unwrap();
+        style.position = UiRect {
+            left: Val::Px(((x - min_x) as f32 + offset_x) * BRICK_SIZE),
+            top: Val::Px(((max_y - y) as f32 + offset_y) * BRICK_SIZE),
+            ..default()
+        };
+        *background = color.into();
+    }
+}
+
+fn update_held_piece(
+    engine: Res<Engine>,
+    colors: Res<TetrominoColors>,
+    query: Query<&Children, With<HeldPiece>>,
+    mut brick_query: PieceBrickQuery,
+) {
+    if !engine.is_changed() && !colors.is_changed() {
+        return;
+    }
+
+    for children in &query {
+        let held = engine.held();
+        let color = match held {
+            Some(tetromino) if engine.can_hold() => colors.get(tetromino),
+            _ => Color::GRAY,
+        };
+
+        show_piece(held, color, children, &mut brick_query);
+    }
+}
+
+fn update_next_pieces(
+    engine: Res<Engine>,
+    colors: Res<TetrominoColors>,
+    mut query: Query<(&NextPiece, &mut Style, &Children), Without<PieceBrick>>,
+    mut brick_query: PieceBrickQuery,
+) {
+    if !engine.is_changed() && !colors.is_changed() {
+        return;
+    }
+
+    let next: Vec<_> = engine.next_pieces().collect();
+    for (next_piece, mut style, children) in &mut query {
+        let tetromino = next.get(next_piece.0).copied();
+        // only take up room for the configured number of pieces
+        style.display = if tetromino.is_some() {
+            Display::Flex
+        } else {
+            Display::None
+        };
+
+        let color = tetromino.map_or(Color::NONE, |tetromino| colors.get(tetromino));
+
+        show_piece(tetromino, color, children, &mut brick_query);
+    }
+}
+
+/// Applies the board dimensions of the next game to the layout and window.
+fn update_layout(
+    settings: Res<Settings>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    mut columns: Query<&mut Style, (With<BoardColumn>, Without<BoardFrame>)>,
+    mut frames: Query<&mut Style, (With<BoardFrame>, Without<BoardColumn>)>,
+) {
+    let config = settings.config();
+    let board = board_size(&config);
+
+    for mut style in &mut columns {
+        style.size.width = Val::Px(board.x);
+    }
+    for mut style in &mut frames {
+        style.size.height = Val::Px(board.y);
+    }
+
+    let size = window_size(&config);
+    for mut window in &mut windows {
+        if window.resolution.width() != size.x || window.resolution.height() != size.y {
+            window.resolution.set(size.x, size.y);
+        }
+    }
+}
+
 fn hide_status(mut query: Query<&mut Visibility, With<StatusText>>) {
     for mut visibility in &mut query {
         *visibility = Visibility::Hidden;
     }
 }
 
-fn show_paused(mut query: Query<(&mut Text, &mut Visibility), With<StatusText>>) {
+fn show_paused(
+    engine: Res<Engine>,
+    mut query: Query<(&mut Text, &mut Visibility), With<StatusText>>,
+) {
     for (mut text, mut visibility) in &mut query {
-        text.sections[0].value = "Game paused\nPress SPACE".into();
+        text.sections.truncate(1);
+        text.sections[0].value = format!("Game paused\nSeed: {}\nPress SPACE", engine.seed());
         *visibility = Visibility::Visible;
     }
 }
 
-fn show_game_over(mut query: Query<(&mut Text, &mut Visibility), With<StatusText>>) {
+fn update_countdown(
+    engine: Res<Engine>,
+    mut query: Query<(&mut Text, &mut Visibility), With<StatusText>>,
+) {
+    if !engine.is_changed() {
+        return;
+    }
+
+    let countdown = engine.countdown();
     for (mut text, mut visibility) in &mut query {
-        text.sections[0].value = "Game over\nPress SPACE".into();
+        match countdown {
+            Some(remaining) => {
+                text.sections.truncate(1);
+                text.sections[0].value = format!("{}", remaining.as_secs_f32().ceil());
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
+
+fn show_game_over(
+    engine: Res<Engine>,
+    mut bests: ResMut<PersonalBests>,
+    mut query: Query<(&mut Text, &mut Visibility), With<StatusText>>,
+) {
+    let Some(summary) = engine.summary() else {
+        return;
+    };
+
+    let title = match summary.outcome {
+        Outcome::Completed => "Complete!",
+        Outcome::TimeUp => "Time up",
+        Outcome::ToppedOut(_) => "Game over",
+    };
+    let mut results: String = summary
+        .entries
+        .iter()
+        .map(|(label, value)| format!("\n{label}: {value}"))
+        .collect();
+
+    if let Some(record) = engine.record() {
+        match bests.submit(engine.mode().key(), record) {
+            Some(best) if !record.beats(&best) => results += &format!("\n\nBest: {best}"),
+            _ => results += "\n\nNew personal best!",
+        }
+    }
+
+    for (mut text, mut visibility) in &mut query {
+        text.sections.truncate(1);
+        text.sections[0].value = format!("{title}\n");
+
+        let style = TextStyle {
+            font_size: 30.0,
+            ..text.sections[0].style.clone()
+        };
+        text.sections.push(TextSection::new(
+            format!(
+                "{}\n\n{results}\n\nSeed: {}\nPress SPACE",
+                engine.mode().name(),
+                engine.seed()
+            ),
+            style,
+        ));
         *visibility = Visibility::Visible;
     }
 }
 
-fn update_statistics(mut query: Query<(&mut Text, With<StatisticsText>)>, res: Res<GameStats>) {
+fn update_banner(
+    time: Res<Time>,
+    mut clears: EventReader<LinesCleared>,
+    mut query: Query<(&mut Text, &mut Visibility, &mut Banner)>,
+) {
+    let perfect_clear = clears.iter().any(|clear| clear.perfect_clear);
+
+    for (mut text, mut visibility, mut banner) in &mut query {
+        if perfect_clear {
+            text.sections[0].value = "Perfect clear!".into();
+            *visibility = Visibility::Visible;
+            banner.0.reset();
+        } else if banner.0.tick(time.delta()).just_finished() {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
+
+fn hide_banner(mut query: Query<&mut Visibility, With<Banner>>) {
+    for mut visibility in &mut query {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn update_statistics(
+    mut query: Query<(&mut Text, With<StatisticsText>)>,
+    res: Res<GameStats>,
+    score: Res<Score>,
+    engine: Res<Engine>,
+) {
+    // timed modes count down instead, so the player can focus on the clock
+    if let Some(time_left) = engine.time_left() {
+        for (mut text, _) in &mut query {
+            text.sections[0].value = format!(
+                "Time left:\n{}\n\nScore: {}\nLines: {}",
+                format_time(time_left),
+                score.score(),
+                score.lines()
+            );
+        }
+        return;
+    }
+
     for (mut text, _) in &mut query {
         text.sections[0].value = format!(
-            "Shapes spawned: {}\n\nLines removed:\n1: {}\n2: {}\n3: {}\n4: {}",
+            "Time: {}\nScore: {}\nLevel: {}\nLines: {}\n\nShapes spawned: {}\n\nLines removed:\n1: {}\n2: {}\n3: {}\n4: {}",
+            format_time(engine.time()),
+            score.score(),
+            score.level(),
+            score.lines(),
             res.shapes_spawned,
             res.lines_removed.0[0],
             res.lines_removed.0[1],